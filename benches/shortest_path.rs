@@ -0,0 +1,385 @@
+use celeb_shortest_distance::data::synthetic::random_credits;
+use celeb_shortest_distance::data::{MovieDB, MovieDBBuilder};
+use celeb_shortest_distance::graph::{
+    Graph, Landmarks, Node, ParallelOptions, SearchLimits, SearchOptions, landmark_path,
+    parallel_shortest_path, shortest_path,
+};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::collections::HashMap;
+use std::hint::black_box;
+
+/// Credits of one random graph of `num_actors` actors in movies of two or
+/// three, or of two separate graphs of half the size each when `connected`
+/// is false.
+fn make_synthetic_pairs(num_actors: usize, connected: bool) -> Vec<(usize, usize)> {
+    if connected {
+        random_credits(0, num_actors, 3)
+    } else {
+        let mut pairs = random_credits(0, num_actors / 2, 3);
+        pairs.extend(random_credits(num_actors / 2, num_actors / 2, 3));
+        pairs
+    }
+}
+
+fn make_synthetic_db(pairs: &[(usize, usize)]) -> MovieDB {
+    MovieDB {
+        connections: MovieDBBuilder::get_connections(pairs.to_vec()),
+        actors: HashMap::new(),
+        movies: HashMap::new(),
+    }
+}
+
+/// The search as it was before the rewrite of `Graph` and the CSR layout of
+/// `MovieDB`: a BFS from the source alone, with credits kept in hash maps of
+/// hash sets, the frontier in a `Vec` popped from the front and explored
+/// actors in a `Vec` checked with `contains`. Copied as it was, less the
+/// printing of the number of nodes.
+mod legacy {
+    use std::collections::{HashMap, HashSet};
+
+    pub struct MovieDB {
+        pub actor_to_movies: HashMap<usize, HashSet<usize>>,
+        pub movie_to_actors: HashMap<usize, HashSet<usize>>,
+    }
+
+    impl MovieDB {
+        pub fn from_pairs(pairs: &[(usize, usize)]) -> MovieDB {
+            let mut actor_to_movies = HashMap::new();
+            let mut movie_to_actors = HashMap::new();
+            for &(actor_id, movie_id) in pairs {
+                actor_to_movies
+                    .entry(actor_id)
+                    .or_insert(HashSet::new())
+                    .insert(movie_id);
+                movie_to_actors
+                    .entry(movie_id)
+                    .or_insert(HashSet::new())
+                    .insert(actor_id);
+            }
+            MovieDB {
+                actor_to_movies,
+                movie_to_actors,
+            }
+        }
+    }
+
+    type NodeIndex = usize;
+    type ActorId = usize;
+    type MovieId = usize;
+
+    // the movies are only read when printing a path
+    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
+    pub struct Node {
+        pub actor_id: ActorId,
+        parent_index: Option<NodeIndex>,
+        pub movie_id: Option<MovieId>,
+    }
+
+    pub struct Graph {
+        frontier: Vec<NodeIndex>,
+        explored: Vec<ActorId>,
+        nodes: Vec<Node>,
+    }
+
+    impl Node {
+        pub fn new(
+            actor_id: usize,
+            parent_index: Option<NodeIndex>,
+            movie_id: Option<usize>,
+        ) -> Node {
+            Node {
+                actor_id,
+                parent_index,
+                movie_id,
+            }
+        }
+    }
+
+    impl Default for Graph {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Graph {
+        pub fn new() -> Graph {
+            Graph {
+                frontier: Vec::new(),
+                explored: Vec::new(),
+                nodes: Vec::new(),
+            }
+        }
+
+        pub fn add_node(&mut self, node: Node) {
+            let max_index = self.nodes.len();
+            self.nodes.push(node);
+            self.frontier.push(max_index);
+        }
+
+        pub fn add_nodes(&mut self, nodes: Vec<Node>) {
+            for node in nodes {
+                self.add_node(node);
+            }
+        }
+
+        pub fn get_neighbours(&self, node_index: NodeIndex, movie_db: &MovieDB) -> Vec<Node> {
+            let parent_actor_id = self.nodes[node_index].actor_id;
+            let mut neighbours = Vec::new();
+            let movies_of_actor = movie_db
+                .actor_to_movies
+                .get(&parent_actor_id)
+                .expect("actor not found");
+            for movie_id in movies_of_actor {
+                let actors_of_movie = movie_db
+                    .movie_to_actors
+                    .get(movie_id)
+                    .expect("movie not found");
+                for actor_id in actors_of_movie {
+                    if self.explored.contains(actor_id) {
+                        continue;
+                    }
+                    let node = Node::new(*actor_id, Some(node_index), Some(*movie_id));
+                    neighbours.push(node);
+                }
+            }
+            neighbours
+        }
+
+        pub fn get_path_to_root(&self, mut node_index: NodeIndex) -> Vec<Node> {
+            let mut path = Vec::new();
+            while let Some(parent_id) = self.nodes[node_index].parent_index {
+                path.push(self.nodes[node_index].clone());
+                node_index = parent_id;
+            }
+            path.push(self.nodes[node_index].clone());
+            path.reverse();
+            path
+        }
+    }
+
+    pub async fn shortest_path(
+        source_actor_id: ActorId,
+        target_actor_id: ActorId,
+        movie_db: &MovieDB,
+    ) -> Result<Vec<Node>, &'static str> {
+        let mut graph = Graph::new();
+        let source_node = Node::new(source_actor_id, None, None);
+        graph.add_node(source_node);
+        let mut num_explored: usize = 0;
+        while !graph.frontier.is_empty() {
+            let node_index = graph.frontier.remove(0);
+            let node = &graph.nodes[node_index];
+            graph.explored.push(node.actor_id);
+
+            if num_explored.is_multiple_of(1000) {
+                tokio::task::yield_now().await;
+            }
+
+            let neighbours = graph.get_neighbours(node_index, movie_db);
+            if let Some(neighbour) = neighbours
+                .iter()
+                .find(|neighbour| neighbour.actor_id == target_actor_id)
+            {
+                graph.add_node(neighbour.clone());
+                let path = graph.get_path_to_root(graph.nodes.len() - 1);
+                return Ok(path);
+            }
+
+            graph.add_nodes(neighbours);
+            num_explored += 1;
+        }
+        Err("no path found")
+    }
+}
+
+/// The same one-sided BFS as [`legacy::shortest_path`] on the current
+/// [`Graph`], which queues actors in a `VecDeque` and marks them in a dense
+/// visited array, reading the credits from the CSR arrays.
+fn graph_bfs(source: usize, target: usize, movie_db: &MovieDB) -> Option<Vec<Node>> {
+    let connections = &movie_db.connections;
+    let options = SearchOptions::default();
+    let target_index = connections.actor_index(target)?;
+    let mut graph = Graph::new(connections.num_actors());
+    let source_node = Node::new(source, None, None);
+    let mut frontier = vec![graph.add_node(connections.actor_index(source)?, source_node)?];
+    while !frontier.is_empty() {
+        let mut next = Vec::new();
+        for node_index in frontier {
+            for (actor, new_node) in graph.add_neighbours(node_index, movie_db, &options) {
+                if actor == target_index {
+                    return Some(graph.get_path_to_root(new_node));
+                }
+                next.push(new_node);
+            }
+        }
+        frontier = next;
+    }
+    None
+}
+
+/// The old BFS against the same BFS on the current `Graph`, both from the
+/// source alone, so the difference is down to `Graph` and the layout of the
+/// credits rather than to the bidirectional search or the components.
+fn bench_one_sided_bfs(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("one_sided_bfs");
+    group.sample_size(10);
+
+    for num_actors in [5_000, 20_000] {
+        let (source, target) = (0, num_actors - 1);
+
+        let pairs = make_synthetic_pairs(num_actors, true);
+        let db = make_synthetic_db(&pairs);
+        let legacy_db = legacy::MovieDB::from_pairs(&pairs);
+        // both implementations must agree before we time them
+        let path = graph_bfs(source, target, &db).unwrap();
+        let legacy_path = runtime
+            .block_on(legacy::shortest_path(source, target, &legacy_db))
+            .unwrap();
+        assert_eq!(legacy_path.len(), path.len());
+        group.bench_with_input(
+            BenchmarkId::new("legacy", num_actors),
+            &legacy_db,
+            |b, db| {
+                b.iter(|| {
+                    runtime.block_on(legacy::shortest_path(
+                        black_box(source),
+                        black_box(target),
+                        db,
+                    ))
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("graph", num_actors), &db, |b, db| {
+            b.iter(|| graph_bfs(black_box(source), black_box(target), db))
+        });
+
+        // no path: both have to exhaust the source's component
+        let pairs = make_synthetic_pairs(num_actors, false);
+        let db = make_synthetic_db(&pairs);
+        assert!(graph_bfs(source, target, &db).is_none());
+        // which takes the legacy search quadratic time, too long to sample on
+        // the bigger graph
+        if num_actors <= 5_000 {
+            let legacy_db = legacy::MovieDB::from_pairs(&pairs);
+            assert!(
+                runtime
+                    .block_on(legacy::shortest_path(source, target, &legacy_db))
+                    .is_err()
+            );
+            group.bench_with_input(
+                BenchmarkId::new("legacy_no_path", num_actors),
+                &legacy_db,
+                |b, db| {
+                    b.iter(|| {
+                        runtime.block_on(legacy::shortest_path(
+                            black_box(source),
+                            black_box(target),
+                            db,
+                        ))
+                    })
+                },
+            );
+        }
+        group.bench_with_input(
+            BenchmarkId::new("graph_no_path", num_actors),
+            &db,
+            |b, db| b.iter(|| graph_bfs(black_box(source), black_box(target), db)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_shortest_path(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("shortest_path");
+    group.sample_size(10);
+
+    let options = SearchOptions::default();
+    let limits = SearchLimits::default();
+    for num_actors in [5_000, 20_000] {
+        let (source, target) = (0, num_actors - 1);
+
+        let pairs = make_synthetic_pairs(num_actors, true);
+        let db = make_synthetic_db(&pairs);
+        let path = runtime
+            .block_on(shortest_path(source, target, &db, &options, &limits))
+            .unwrap()
+            .0;
+        group.bench_with_input(BenchmarkId::new("current", num_actors), &db, |b, db| {
+            b.iter(|| {
+                runtime.block_on(shortest_path(
+                    black_box(source),
+                    black_box(target),
+                    db,
+                    &options,
+                    &limits,
+                ))
+            })
+        });
+        let landmarks = Landmarks::select(&db, 8);
+        let landmark_path_found = runtime
+            .block_on(landmark_path(source, target, &landmarks, &db, &options))
+            .unwrap();
+        assert_eq!(landmark_path_found.len(), path.len());
+        group.bench_with_input(BenchmarkId::new("landmarks", num_actors), &db, |b, db| {
+            b.iter(|| {
+                runtime.block_on(landmark_path(
+                    black_box(source),
+                    black_box(target),
+                    &landmarks,
+                    db,
+                    &options,
+                ))
+            })
+        });
+        let parallel = ParallelOptions {
+            min_actors_per_thread: 64,
+            ..ParallelOptions::new(4).unwrap()
+        };
+        let parallel_path =
+            parallel_shortest_path(source, target, &db, &options, &limits, &parallel)
+                .unwrap()
+                .0;
+        assert_eq!(parallel_path.len(), path.len());
+        group.bench_with_input(BenchmarkId::new("parallel", num_actors), &db, |b, db| {
+            b.iter(|| {
+                parallel_shortest_path(
+                    black_box(source),
+                    black_box(target),
+                    db,
+                    &options,
+                    &limits,
+                    &parallel,
+                )
+            })
+        });
+
+        // no path: the search sees the actors are in different components
+        let db = make_synthetic_db(&make_synthetic_pairs(num_actors, false));
+        group.bench_with_input(
+            BenchmarkId::new("current_no_path", num_actors),
+            &db,
+            |b, db| {
+                b.iter(|| {
+                    runtime.block_on(shortest_path(
+                        black_box(source),
+                        black_box(target),
+                        db,
+                        &options,
+                        &limits,
+                    ))
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_one_sided_bfs, bench_shortest_path);
+criterion_main!(benches);
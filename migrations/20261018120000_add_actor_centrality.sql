@@ -0,0 +1,7 @@
+-- Centrality of each actor, written by `celeb_search <path> centrality` and
+-- loaded with scripts/load_centrality.sh, to rank search results
+CREATE TABLE actor_centrality (
+    id INTEGER PRIMARY KEY,
+    closeness REAL NOT NULL,
+    betweenness REAL NOT NULL
+);
@@ -0,0 +1,22 @@
+#!/bin/bash
+# Script to load the centrality of every actor into actors.db, so that
+# searching for actors by name lists the most central ones first.
+# Make the CSV with:
+#   celeb_search data/new_large centrality --samples 1000 > data/new_large/centrality.csv
+# and run the migrations first (see scripts/init_actor_db.sh).
+
+# set exit on error and find pipe errors
+set -eo pipefail
+
+CENTRALITY_CSV="${1:-data/new_large/centrality.csv}"
+
+sqlite3 actors.db <<EOF_SQL
+.mode csv
+.separator ","
+.import ${CENTRALITY_CSV} temp_centrality
+DELETE FROM actor_centrality;
+INSERT INTO actor_centrality (id, closeness, betweenness)
+SELECT CAST(actor_id AS INTEGER), CAST(closeness AS REAL), CAST(betweenness AS REAL)
+FROM temp_centrality;
+DROP TABLE temp_centrality;
+EOF_SQL
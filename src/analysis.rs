@@ -0,0 +1,73 @@
+//! Whole-graph statistics of the co-star graph, such as its diameter.
+//!
+//! Unlike the searches in [`crate::graph`], these visit most of the graph
+//! many times over and are meant for offline reports, so they are plain
+//! blocking functions.
+use crate::data::{ActorIndex, Connections};
+
+mod centrality;
+mod diameter;
+
+pub use centrality::{ActorCentrality, CentralityOptions, centrality};
+pub use diameter::{DiameterEstimate, estimate_diameter};
+
+/// Distance of actors not reached by a BFS.
+const UNREACHED: u32 = u32::MAX;
+
+/// Breadth first search over actor indices, reusing its buffers between runs.
+struct Bfs {
+    /// Hops from the source by actor index, [`UNREACHED`] for the others.
+    distances: Vec<u32>,
+    /// Actors in the order they were reached, the source first.
+    order: Vec<ActorIndex>,
+}
+
+impl Bfs {
+    fn new(num_actors: usize) -> Bfs {
+        Bfs {
+            distances: vec![UNREACHED; num_actors],
+            order: Vec::with_capacity(num_actors),
+        }
+    }
+
+    /// Runs a BFS from `source` and returns its eccentricity, the largest
+    /// distance to an actor it reaches.
+    fn run(&mut self, connections: &Connections, source: ActorIndex) -> u32 {
+        for &actor in &self.order {
+            self.distances[actor as usize] = UNREACHED;
+        }
+        self.order.clear();
+
+        self.distances[source as usize] = 0;
+        self.order.push(source);
+        let mut next = 0;
+        while next < self.order.len() {
+            let actor = self.order[next];
+            next += 1;
+            let distance = self.distances[actor as usize] + 1;
+            for &movie in connections.movies_of(actor) {
+                for &co_star in connections.cast_of(movie) {
+                    if self.distances[co_star as usize] == UNREACHED {
+                        self.distances[co_star as usize] = distance;
+                        self.order.push(co_star);
+                    }
+                }
+            }
+        }
+        self.distances[*self.order.last().unwrap() as usize]
+    }
+
+    fn distance(&self, actor: ActorIndex) -> u32 {
+        self.distances[actor as usize]
+    }
+
+    /// Actors of the last run at its largest distance.
+    fn farthest(&self) -> impl Iterator<Item = ActorIndex> + '_ {
+        let eccentricity = self.distance(*self.order.last().unwrap());
+        self.order
+            .iter()
+            .rev()
+            .copied()
+            .take_while(move |&actor| self.distance(actor) == eccentricity)
+    }
+}
@@ -0,0 +1,338 @@
+use super::UNREACHED;
+use crate::data::{ActorIndex, Connections, MovieDB};
+use serde::Serialize;
+use std::thread;
+
+/// How [`centrality`] picks its BFS sources and spreads them over threads.
+#[derive(Debug, Clone)]
+pub struct CentralityOptions {
+    /// Number of actors to run a BFS from, picked at random. `None`, or as
+    /// many as there are actors, runs one from every actor for exact values.
+    pub num_samples: Option<usize>,
+    pub num_threads: usize,
+    /// Seed of the sampling, so runs can be repeated.
+    pub seed: u64,
+}
+
+impl Default for CentralityOptions {
+    fn default() -> Self {
+        CentralityOptions {
+            num_samples: None,
+            num_threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            seed: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+}
+
+/// Centrality of one actor in the graph where actors are linked when they
+/// share at least one movie.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActorCentrality {
+    pub actor_id: usize,
+    /// Closeness in the Wasserman–Faust form, which stays comparable across
+    /// components: `(r - 1) / s * (r - 1) / (n - 1)` for an actor who
+    /// reaches `r` actors at `s` hops in total, out of `n` actors. Between 0
+    /// and 1, and 0 for an actor alone in their component.
+    pub closeness: f64,
+    /// Number of pairs of other actors whose shortest paths go through the
+    /// actor, counting a pair with several shortest paths in proportion.
+    pub betweenness: f64,
+}
+
+/// Closeness and betweenness centrality of every actor with credits, in
+/// actor id order.
+///
+/// Runs Brandes' algorithm from every actor, or from a random sample of
+/// actors as in Brandes and Pich, scaling the sums up to the whole graph.
+/// The same BFS gives the distances for closeness, estimated per component
+/// from the sources that fall in it as in Eppstein and Wang. An actor in a
+/// component no source fell in gets a closeness of 0. Sources are split
+/// evenly over the threads, and the number of threads only changes the
+/// results by rounding.
+pub fn centrality(movie_db: &MovieDB, options: &CentralityOptions) -> Vec<ActorCentrality> {
+    let connections = &movie_db.connections;
+    let num_actors = connections.num_actors();
+    let sources = pick_sources(num_actors, options);
+
+    let num_threads = options.num_threads.clamp(1, sources.len().max(1));
+    let chunk_size = sources.len().div_ceil(num_threads).max(1);
+    let partial_sums: Vec<Sums> = thread::scope(|scope| {
+        let handles: Vec<_> = sources
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || Sums::from_sources(connections, chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    let mut sums = Sums::new(num_actors);
+    for partial in partial_sums {
+        sums.add(&partial);
+    }
+
+    // sources that fell in each component
+    let components = connections.components();
+    let mut sources_in = vec![0usize; components.num_components()];
+    for &source in &sources {
+        sources_in[components.component_of(source) as usize] += 1;
+    }
+
+    let scale = num_actors as f64 / sources.len().max(1) as f64;
+    (0..num_actors as ActorIndex)
+        .map(|actor| {
+            let component = components.component_of(actor);
+            let reach = components.size(component) as f64;
+            let sampled = sources_in[component as usize] as f64;
+            let total_distance = reach / sampled * sums.distances[actor as usize] as f64;
+            let closeness = if sampled == 0.0 || total_distance == 0.0 {
+                0.0
+            } else {
+                (reach - 1.0) / total_distance * (reach - 1.0) / (num_actors as f64 - 1.0)
+            };
+            ActorCentrality {
+                actor_id: connections.actor_id(actor),
+                closeness,
+                // every pair is counted once from each end
+                betweenness: scale * sums.dependencies[actor as usize] / 2.0,
+            }
+        })
+        .collect()
+}
+
+/// Every actor, or a sample of them drawn without replacement with a
+/// xorshift generator, in ascending order.
+fn pick_sources(num_actors: usize, options: &CentralityOptions) -> Vec<ActorIndex> {
+    let mut actors: Vec<ActorIndex> = (0..num_actors as ActorIndex).collect();
+    let num_samples = options.num_samples.unwrap_or(num_actors);
+    if num_samples >= num_actors {
+        return actors;
+    }
+    let mut state = options.seed.max(1);
+    for i in 0..num_samples {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let j = i + (state % (num_actors - i) as u64) as usize;
+        actors.swap(i, j);
+    }
+    actors.truncate(num_samples);
+    actors.sort_unstable();
+    actors
+}
+
+/// What the BFS runs from some of the sources add up to.
+struct Sums {
+    /// Brandes dependencies of every actor on the sources.
+    dependencies: Vec<f64>,
+    /// Hops from the sources to every actor.
+    distances: Vec<u64>,
+}
+
+impl Sums {
+    fn new(num_actors: usize) -> Sums {
+        Sums {
+            dependencies: vec![0.0; num_actors],
+            distances: vec![0; num_actors],
+        }
+    }
+
+    fn add(&mut self, other: &Sums) {
+        for (sum, value) in self.dependencies.iter_mut().zip(&other.dependencies) {
+            *sum += value;
+        }
+        for (sum, value) in self.distances.iter_mut().zip(&other.distances) {
+            *sum += value;
+        }
+    }
+
+    fn from_sources(connections: &Connections, sources: &[ActorIndex]) -> Sums {
+        let num_actors = connections.num_actors();
+        let mut sums = Sums::new(num_actors);
+        let mut distance = vec![UNREACHED; num_actors];
+        let mut num_paths = vec![0.0f64; num_actors];
+        let mut dependency = vec![0.0f64; num_actors];
+        let mut order: Vec<ActorIndex> = Vec::new();
+        // actors sharing several movies are linked once: `stamp[w]` is the
+        // expansion that last met `w`
+        let mut stamp = vec![0u64; num_actors];
+        let mut next_stamp = 0u64;
+
+        for &source in sources {
+            distance[source as usize] = 0;
+            num_paths[source as usize] = 1.0;
+            order.push(source);
+            let mut next = 0;
+            while next < order.len() {
+                let actor = order[next];
+                next += 1;
+                next_stamp += 1;
+                let next_distance = distance[actor as usize] + 1;
+                for co_star in co_stars(connections, actor) {
+                    if stamp[co_star as usize] == next_stamp {
+                        continue;
+                    }
+                    stamp[co_star as usize] = next_stamp;
+                    if distance[co_star as usize] == UNREACHED {
+                        distance[co_star as usize] = next_distance;
+                        order.push(co_star);
+                    }
+                    if distance[co_star as usize] == next_distance {
+                        num_paths[co_star as usize] += num_paths[actor as usize];
+                    }
+                }
+            }
+
+            // walk back from the farthest actors, handing each actor's
+            // dependency to its predecessors on shortest paths
+            for &actor in order.iter().rev() {
+                next_stamp += 1;
+                let previous_distance = distance[actor as usize].wrapping_sub(1);
+                let share = (1.0 + dependency[actor as usize]) / num_paths[actor as usize];
+                for co_star in co_stars(connections, actor) {
+                    if stamp[co_star as usize] == next_stamp
+                        || distance[co_star as usize] != previous_distance
+                    {
+                        continue;
+                    }
+                    stamp[co_star as usize] = next_stamp;
+                    dependency[co_star as usize] += num_paths[co_star as usize] * share;
+                }
+                if actor != source {
+                    sums.dependencies[actor as usize] += dependency[actor as usize];
+                }
+                sums.distances[actor as usize] += distance[actor as usize] as u64;
+            }
+
+            for &actor in &order {
+                distance[actor as usize] = UNREACHED;
+                num_paths[actor as usize] = 0.0;
+                dependency[actor as usize] = 0.0;
+            }
+            order.clear();
+        }
+        sums
+    }
+}
+
+/// Co-stars of the actor, the actor included, once per shared movie.
+fn co_stars(connections: &Connections, actor: ActorIndex) -> impl Iterator<Item = ActorIndex> + '_ {
+    connections
+        .movies_of(actor)
+        .iter()
+        .flat_map(|&movie| connections.cast_of(movie).iter().copied())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::test::{make_db, make_test_db};
+    use std::collections::HashMap;
+
+    fn exact(num_threads: usize) -> CentralityOptions {
+        CentralityOptions {
+            num_threads,
+            ..Default::default()
+        }
+    }
+
+    fn by_id(rows: Vec<ActorCentrality>) -> HashMap<usize, ActorCentrality> {
+        rows.into_iter().map(|row| (row.actor_id, row)).collect()
+    }
+
+    #[test]
+    fn centrality_of_a_chain() {
+        // 1 - 2 - 3, with 1 and 2 sharing two movies
+        let db = make_db(vec![(1, 10), (2, 10), (1, 11), (2, 11), (2, 12), (3, 12)]);
+        let rows = by_id(centrality(&db, &exact(1)));
+        assert_eq!(rows[&1].betweenness, 0.0);
+        assert_eq!(rows[&2].betweenness, 1.0);
+        assert_eq!(rows[&3].betweenness, 0.0);
+        assert_eq!(rows[&2].closeness, 1.0);
+        assert_eq!(rows[&1].closeness, 2.0 / 3.0);
+    }
+
+    #[test]
+    fn centrality_split_paths() {
+        // a ring of four: 1 - 2 - 4 - 3 - 1, so every pair of opposite
+        // actors has two shortest paths
+        let db = make_db(vec![
+            (1, 10),
+            (2, 10),
+            (1, 11),
+            (3, 11),
+            (2, 12),
+            (4, 12),
+            (3, 13),
+            (4, 13),
+        ]);
+        let rows = by_id(centrality(&db, &exact(2)));
+        for actor_id in 1..=4 {
+            assert_eq!(rows[&actor_id].betweenness, 0.5);
+            assert_eq!(rows[&actor_id].closeness, 3.0 / 4.0);
+        }
+    }
+
+    #[test]
+    fn centrality_small_dataset() {
+        let db = make_test_db();
+        let rows = centrality(&db, &exact(1));
+        assert_eq!(rows.len(), 15);
+        let top = rows
+            .iter()
+            .max_by(|a, b| a.betweenness.total_cmp(&b.betweenness))
+            .unwrap();
+        // Kevin Bacon links the six actors on Tom Cruise's side to the eight
+        // on Tom Hanks' side
+        assert_eq!(top.actor_id, 102);
+        assert!((top.betweenness - 48.0).abs() < 1e-9);
+        let top = rows
+            .iter()
+            .max_by(|a, b| a.closeness.total_cmp(&b.closeness))
+            .unwrap();
+        assert_eq!(top.actor_id, 102);
+
+        // the same with more threads
+        let threaded = centrality(&db, &exact(4));
+        for (row, other) in rows.iter().zip(&threaded) {
+            assert_eq!(row.actor_id, other.actor_id);
+            assert!((row.betweenness - other.betweenness).abs() < 1e-9);
+            assert!((row.closeness - other.closeness).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn centrality_sampled() {
+        let db = make_test_db();
+        let options = CentralityOptions {
+            num_samples: Some(15),
+            ..exact(1)
+        };
+        // sampling every actor is exact
+        assert_eq!(centrality(&db, &options), centrality(&db, &exact(1)));
+
+        let options = CentralityOptions {
+            num_samples: Some(5),
+            num_threads: 2,
+            seed: 7,
+        };
+        let rows = centrality(&db, &options);
+        assert_eq!(rows, centrality(&db, &options));
+        for row in &rows {
+            assert!(row.betweenness >= 0.0);
+            assert!((0.0..=1.0).contains(&row.closeness));
+        }
+        assert_eq!(pick_sources(15, &options).len(), 5);
+    }
+
+    #[test]
+    fn centrality_separate_components() {
+        // a pair of co-stars, and a chain of three
+        let db = make_db(vec![(1, 10), (2, 10), (3, 11), (4, 11), (4, 12), (5, 12)]);
+        let rows = by_id(centrality(&db, &exact(1)));
+        // one co-star out of the four other actors
+        assert_eq!(rows[&1].closeness, 1.0 / 4.0);
+        assert_eq!(rows[&4].closeness, 2.0 / 2.0 * 2.0 / 4.0);
+        assert_eq!(rows[&4].betweenness, 1.0);
+    }
+}
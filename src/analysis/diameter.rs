@@ -0,0 +1,313 @@
+use super::{Bfs, UNREACHED};
+use crate::data::{ActorIndex, ComponentId, Connections, MovieDB};
+use crate::graph::Node;
+use std::collections::{HashMap, HashSet};
+
+/// Number of sweeps from the farthest actor of the previous sweep before
+/// choosing sources by their eccentricity bounds.
+const DOUBLE_SWEEP_STEPS: usize = 4;
+/// Most actor pairs at the diameter kept in an estimate.
+const MAX_PERIPHERAL_PAIRS: usize = 100;
+
+/// Diameter, radius and eccentricities of one connected component of the
+/// co-star graph, as bounds that meet once the estimate is exact.
+///
+/// The eccentricity of an actor is the most hops between them and any actor
+/// in their component; the diameter and radius are the largest and smallest
+/// eccentricities.
+#[derive(Debug, Clone)]
+pub struct DiameterEstimate {
+    pub component: ComponentId,
+    /// Actors in the component.
+    pub num_actors: usize,
+    /// Longest shortest path found, a lower bound on the diameter.
+    pub diameter: u32,
+    pub diameter_upper_bound: u32,
+    /// Smallest eccentricity bound found, an upper bound on the radius.
+    pub radius: u32,
+    pub radius_lower_bound: u32,
+    /// BFS runs it took.
+    pub num_sweeps: usize,
+    /// Actors `diameter` hops apart, up to a hundred pairs, smallest ids
+    /// first within a pair.
+    pub peripheral_pairs: Vec<(usize, usize)>,
+    /// A shortest path between the first peripheral pair, or the only actor
+    /// of a component of one.
+    pub diameter_path: Vec<Node>,
+    eccentricity_bounds: HashMap<usize, (u32, u32)>,
+}
+
+impl DiameterEstimate {
+    /// Whether the bounds on both the diameter and the radius have met.
+    pub fn is_exact(&self) -> bool {
+        self.diameter == self.diameter_upper_bound && self.radius == self.radius_lower_bound
+    }
+
+    /// Lower and upper bound on the eccentricity of an actor of the
+    /// component.
+    pub fn eccentricity(&self, actor_id: usize) -> Option<(u32, u32)> {
+        self.eccentricity_bounds.get(&actor_id).copied()
+    }
+}
+
+/// Estimates the diameter, radius and eccentricities of a connected
+/// component of the co-star graph with at most `max_sweeps` BFS runs.
+/// Component 0 is the largest one.
+///
+/// Starts with an iterative double sweep from the actor with the most
+/// credits: each BFS starts from an actor farthest from the previous source,
+/// which quickly finds a path close to the diameter. Every BFS from `v` also
+/// bounds the eccentricity of each actor `w` between `max(d(v, w), ecc(v) -
+/// d(v, w))` and `ecc(v) + d(v, w)`. Later sweeps alternate between the
+/// actors with the highest upper bound and the lowest lower bound, and skip
+/// actors whose bounds can no longer change the diameter or the radius, as
+/// in Takes and Kosters' BoundingDiameters. On co-star graphs this usually
+/// makes the diameter and radius exact within a few dozen sweeps.
+///
+/// Returns `None` if there is no such component.
+pub fn estimate_diameter(
+    movie_db: &MovieDB,
+    component: ComponentId,
+    max_sweeps: usize,
+) -> Option<DiameterEstimate> {
+    let connections = &movie_db.connections;
+    let components = connections.components();
+    if component as usize >= components.num_components() {
+        return None;
+    }
+    let members: Vec<ActorIndex> = (0..connections.num_actors() as ActorIndex)
+        .filter(|&actor| components.component_of(actor) == component)
+        .collect();
+    let num_credits = |actor: ActorIndex| connections.movies_of(actor).len();
+
+    let mut lower = vec![0; connections.num_actors()];
+    let mut upper = vec![UNREACHED; connections.num_actors()];
+    let mut candidates = members.clone();
+    let (mut diameter, mut diameter_upper_bound) = (0, UNREACHED);
+    let (mut radius, mut radius_lower_bound) = (UNREACHED, 0);
+    let mut peripheral_pairs = HashSet::new();
+    let mut bfs = Bfs::new(connections.num_actors());
+    let mut num_sweeps = 0;
+    let mut next_source = members
+        .iter()
+        .copied()
+        .max_by_key(|&actor| num_credits(actor));
+
+    while num_sweeps < max_sweeps && !candidates.is_empty() {
+        let source = match next_source.take() {
+            Some(source) => source,
+            // alternate between the two ends of the eccentricity bounds
+            None if num_sweeps % 2 == 0 => *candidates
+                .iter()
+                .max_by_key(|&&actor| (upper[actor as usize], num_credits(actor)))
+                .unwrap(),
+            None => *candidates
+                .iter()
+                .min_by_key(|&&actor| (lower[actor as usize], usize::MAX - num_credits(actor)))
+                .unwrap(),
+        };
+        let eccentricity = bfs.run(connections, source);
+        num_sweeps += 1;
+
+        if eccentricity > diameter {
+            diameter = eccentricity;
+            peripheral_pairs.clear();
+        }
+        if eccentricity == diameter && eccentricity > 0 {
+            for far in bfs.farthest() {
+                if peripheral_pairs.len() == MAX_PERIPHERAL_PAIRS {
+                    break;
+                }
+                let (a, b) = (connections.actor_id(source), connections.actor_id(far));
+                peripheral_pairs.insert((a.min(b), a.max(b)));
+            }
+        }
+        radius = radius.min(eccentricity);
+
+        diameter_upper_bound = 0;
+        radius_lower_bound = UNREACHED;
+        for &actor in &members {
+            let distance = bfs.distance(actor);
+            let index = actor as usize;
+            lower[index] = lower[index].max(distance).max(eccentricity - distance);
+            upper[index] = upper[index].min(eccentricity + distance);
+            diameter_upper_bound = diameter_upper_bound.max(upper[index]);
+            radius_lower_bound = radius_lower_bound.min(lower[index]);
+            radius = radius.min(upper[index]);
+        }
+
+        // drop the actors that can't tighten the bounds any more
+        candidates.retain(|&actor| {
+            let (lower, upper) = (lower[actor as usize], upper[actor as usize]);
+            lower != upper && (upper > diameter || lower < radius)
+        });
+
+        if num_sweeps < DOUBLE_SWEEP_STEPS {
+            next_source = bfs
+                .farthest()
+                .find(|&actor| lower[actor as usize] != upper[actor as usize]);
+        }
+    }
+
+    let mut peripheral_pairs: Vec<(usize, usize)> = peripheral_pairs.into_iter().collect();
+    peripheral_pairs.sort_unstable();
+    let diameter_path = match peripheral_pairs.first() {
+        Some(&(from, to)) => {
+            let from = connections.actor_index(from).unwrap();
+            bfs.run(connections, from);
+            path_to(&bfs, connections, connections.actor_index(to).unwrap())
+        }
+        None => vec![Node::new(connections.actor_id(members[0]), None, None)],
+    };
+    let eccentricity_bounds = members
+        .iter()
+        .map(|&actor| {
+            let bounds = (lower[actor as usize], upper[actor as usize]);
+            (connections.actor_id(actor), bounds)
+        })
+        .collect();
+
+    Some(DiameterEstimate {
+        component,
+        num_actors: members.len(),
+        diameter,
+        diameter_upper_bound,
+        radius,
+        radius_lower_bound,
+        num_sweeps,
+        peripheral_pairs,
+        diameter_path,
+        eccentricity_bounds,
+    })
+}
+
+/// A shortest path from the source of the last BFS to `target`, walking
+/// back through co-stars one hop closer to the source each time.
+fn path_to(bfs: &Bfs, connections: &Connections, target: ActorIndex) -> Vec<Node> {
+    let mut hops = Vec::new();
+    let mut actor = target;
+    while bfs.distance(actor) > 0 {
+        let closer = bfs.distance(actor) - 1;
+        let (movie, co_star) = connections
+            .movies_of(actor)
+            .iter()
+            .flat_map(|&movie| {
+                connections
+                    .cast_of(movie)
+                    .iter()
+                    .map(move |&co_star| (movie, co_star))
+            })
+            .find(|&(_, co_star)| bfs.distance(co_star) == closer)
+            .unwrap();
+        hops.push((actor, movie));
+        actor = co_star;
+    }
+
+    let mut path = vec![Node::new(connections.actor_id(actor), None, None)];
+    for (index, &(actor, movie)) in hops.iter().rev().enumerate() {
+        path.push(Node::new(
+            connections.actor_id(actor),
+            Some(index),
+            Some(connections.movie_id(movie)),
+        ));
+    }
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::test::{assert_chain, make_db, make_test_db};
+
+    /// Eccentricity of every actor of the db, from a BFS per actor.
+    fn brute_force_eccentricities(db: &MovieDB) -> HashMap<usize, u32> {
+        let connections = &db.connections;
+        let mut bfs = Bfs::new(connections.num_actors());
+        (0..connections.num_actors() as ActorIndex)
+            .map(|actor| (connections.actor_id(actor), bfs.run(connections, actor)))
+            .collect()
+    }
+
+    #[test]
+    fn diameter_small_dataset() {
+        let db = make_test_db();
+        let estimate = estimate_diameter(&db, 0, 100).unwrap();
+        assert!(estimate.is_exact());
+        assert_eq!(estimate.num_actors, 15);
+        // the cast of The Princess Bride is five hops from the rest of the
+        // cast of Rain Man
+        assert_eq!(estimate.diameter, 5);
+        assert_eq!(estimate.radius, 3);
+
+        let princess_bride = [144, 1597, 1697];
+        let rain_man = [163, 420, 596520];
+        for &(a, b) in &estimate.peripheral_pairs {
+            assert!(a < b);
+            assert!(
+                princess_bride.contains(&a) && rain_man.contains(&b)
+                    || rain_man.contains(&a) && princess_bride.contains(&b)
+            );
+        }
+
+        let path = &estimate.diameter_path;
+        assert_eq!(path.len(), 6);
+        let (from, to) = estimate.peripheral_pairs[0];
+        assert_eq!(path[0].actor_id, from);
+        assert_eq!(path[5].actor_id, to);
+        assert_chain(path, &db);
+    }
+
+    #[test]
+    fn eccentricity_bounds_hold() {
+        let db = make_test_db();
+        let exact = brute_force_eccentricities(&db);
+        for max_sweeps in 1..6 {
+            let estimate = estimate_diameter(&db, 0, max_sweeps).unwrap();
+            assert!(estimate.num_sweeps <= max_sweeps);
+            assert!(estimate.diameter <= 5 && 5 <= estimate.diameter_upper_bound);
+            assert!(estimate.radius_lower_bound <= 3 && 3 <= estimate.radius);
+            for (actor_id, eccentricity) in &exact {
+                let (lower, upper) = estimate.eccentricity(*actor_id).unwrap();
+                assert!(lower <= *eccentricity && *eccentricity <= upper);
+            }
+        }
+    }
+
+    #[test]
+    fn diameter_of_a_chain() {
+        // actors 1 to 7 in a row, each pair sharing a movie
+        let pairs = (1..7).flat_map(|actor| [(actor, actor), (actor + 1, actor)]);
+        let db = make_db(pairs.collect());
+        let estimate = estimate_diameter(&db, 0, 100).unwrap();
+        assert!(estimate.is_exact());
+        assert_eq!(estimate.diameter, 6);
+        assert_eq!(estimate.radius, 3);
+        assert_eq!(estimate.peripheral_pairs, vec![(1, 7)]);
+        assert_eq!(estimate.eccentricity(4), Some((3, 3)));
+        assert_eq!(estimate.eccentricity(1), Some((6, 6)));
+        let actor_ids: Vec<usize> = estimate
+            .diameter_path
+            .iter()
+            .map(|node| node.actor_id)
+            .collect();
+        assert_eq!(actor_ids, vec![1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn diameter_of_small_components() {
+        // a pair of co-stars and an actor alone in a movie
+        let db = make_db(vec![(1, 10), (2, 10), (3, 11)]);
+        let estimate = estimate_diameter(&db, 0, 100).unwrap();
+        assert_eq!((estimate.diameter, estimate.radius), (1, 1));
+        assert_eq!(estimate.peripheral_pairs, vec![(1, 2)]);
+
+        let estimate = estimate_diameter(&db, 1, 100).unwrap();
+        assert!(estimate.is_exact());
+        assert_eq!((estimate.diameter, estimate.radius), (0, 0));
+        assert!(estimate.peripheral_pairs.is_empty());
+        assert_eq!(estimate.diameter_path.len(), 1);
+        assert_eq!(estimate.diameter_path[0].actor_id, 3);
+
+        assert!(estimate_diameter(&db, 2, 100).is_none());
+    }
+}
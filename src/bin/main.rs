@@ -1,14 +1,125 @@
-use celeb_shortest_distance::data::{MovieDB, MovieDBBuilder};
-use celeb_shortest_distance::graph::shortest_path;
+use celeb_shortest_distance::analysis::{self, CentralityOptions};
+use celeb_shortest_distance::data::{Actor, Connections, Movie, MovieDB, MovieDBBuilder};
+use celeb_shortest_distance::export::{ExportGraph, GraphFormat, write_graph};
+use celeb_shortest_distance::graph::{
+    DistanceOracle, Endpoint, LANDMARKS_FILE, Landmarks, Node, ORACLE_FILE, ParallelOptions,
+    SearchLimits, SearchOptions, Via, YearOrder, all_shortest_paths, chronological_path,
+    distance_profile, endpoint_path, landmark_path, nearest_actor, parallel_shortest_path,
+    shortest_path, via_path,
+};
 use celeb_shortest_distance::interactive_io;
+use celeb_shortest_distance::query;
 
+use serde::Serialize;
+use std::collections::HashMap;
 use std::env;
-use std::io::{stdin, stdout};
+use std::io::{BufWriter, Error as IoError, Write, stdin, stdout};
 use std::path::PathBuf;
-use std::thread;
+use std::process;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// BFS runs `diameter` takes at most unless told otherwise.
+const DEFAULT_MAX_SWEEPS: usize = 100;
+
+/// Landmarks the `landmarks` command picks unless told otherwise.
+const DEFAULT_LANDMARKS: usize = 16;
+
+/// Hops around the actors of an exported subgraph unless told otherwise.
+const DEFAULT_HOPS: u32 = 1;
+
+/// Nodes an exported subgraph may have unless told otherwise.
+const DEFAULT_SUBGRAPH_NODES: usize = 500;
+
+type MovieConnsHandle = JoinHandle<Result<(HashMap<usize, Movie>, Connections), IoError>>;
+
+enum Command {
+    /// Shortest path between two actors.
+    Path,
+    /// Which of the `--target` actors is nearest to the source actor.
+    Nearest,
+    /// Distance from the actor with this id or name to every actor.
+    Distances(String),
+    /// Shortest chain between the movies with these ids or titles.
+    MoviePath(String, String),
+    /// Shortest chain from the actor to the movie.
+    ActorMoviePath(String, String),
+    /// Everyone the actor with this id or name was in a movie with.
+    CoStars(String),
+    /// Movies the two actors were both in.
+    SharedMovies(String, String),
+    /// Actors who were in a movie with each of the two actors.
+    CommonCoStars(String, String),
+    /// Writes the graph, or part of it, for graph tools.
+    Export(ExportScope),
+    /// Sizes of the connected components of the co-star graph.
+    Components,
+    /// Diameter, radius and peripheral actors of the largest component.
+    Diameter,
+    /// Closeness and betweenness of every actor, as CSV.
+    Centrality,
+    /// Precomputes landmark distances for faster path searches.
+    Landmarks,
+    /// Builds the distance oracle that answers path searches instantly.
+    Oracle,
+}
+
+/// What the `export` command writes.
+enum ExportScope {
+    /// Every actor, movie and credit.
+    All,
+    /// A shortest path between the two actors.
+    Path(String, String),
+    /// The neighbourhood of the actors.
+    Subgraph(Vec<String>),
+}
+
+enum OutputFormat {
+    Csv,
+    Json,
+    Graph(GraphFormat),
+}
 
 struct Config {
     db_path: PathBuf,
+    command: Command,
+    /// Ids or names of the actors `nearest` picks from.
+    targets: Vec<String>,
+    /// How `distances` writes its table, or `export` the graph.
+    format: Option<OutputFormat>,
+    /// Most BFS runs `diameter` may take.
+    max_sweeps: Option<usize>,
+    /// Actors `centrality` samples, all of them if unset.
+    samples: Option<usize>,
+    /// Threads of `centrality`, or of a parallel path search.
+    threads: Option<usize>,
+    seed: Option<u64>,
+    /// Number of landmarks the `landmarks` command picks.
+    count: Option<usize>,
+    /// Most hops the path may have.
+    max_depth: Option<usize>,
+    /// Most actors the path search may expand.
+    max_nodes: Option<usize>,
+    /// Most nodes an exported subgraph may have.
+    max_subgraph_nodes: Option<usize>,
+    /// Hops around the actors of an exported subgraph.
+    hops: Option<u32>,
+    /// Print how much work the path search did.
+    verbose: bool,
+    max_paths: Option<usize>,
+    /// Ids or names of actors the path may not go through.
+    exclude_actors: Vec<String>,
+    /// Ids or titles of movies the path may not use.
+    exclude_movies: Vec<String>,
+    max_cast_size: Option<usize>,
+    min_year: Option<u32>,
+    max_year: Option<u32>,
+    /// Only follow movies in release order.
+    year_order: Option<YearOrder>,
+    /// Id or name of an actor the path has to go through.
+    via_actor: Option<String>,
+    /// Id or title of a movie the path has to use.
+    via_movie: Option<String>,
     // source_actor_name: String,
     // target_actor_name: String,
 }
@@ -25,6 +136,347 @@ impl Config {
             None => return Err("Didn't get a db path"),
         };
 
+        let mut args = args.peekable();
+        let command = match args.next_if(|arg| !arg.starts_with("--")) {
+            None => Command::Path,
+            Some(arg) if arg == "nearest" => Command::Nearest,
+            Some(arg) if arg == "components" => Command::Components,
+            Some(arg) if arg == "diameter" => Command::Diameter,
+            Some(arg) if arg == "centrality" => Command::Centrality,
+            Some(arg) if arg == "landmarks" => Command::Landmarks,
+            Some(arg) if arg == "oracle" => Command::Oracle,
+            Some(arg) if arg == "distances" => match args.next() {
+                Some(actor) if !actor.starts_with("--") => Command::Distances(actor),
+                _ => return Err("distances needs an actor id or name"),
+            },
+            Some(arg) if arg == "export" => match args.next_if(|arg| !arg.starts_with("--")) {
+                None => Command::Export(ExportScope::All),
+                Some(arg) if arg == "path" => match (
+                    args.next_if(|arg| !arg.starts_with("--")),
+                    args.next_if(|arg| !arg.starts_with("--")),
+                ) {
+                    (Some(actor_1), Some(actor_2)) => {
+                        Command::Export(ExportScope::Path(actor_1, actor_2))
+                    }
+                    _ => return Err("export path needs two actor ids or names"),
+                },
+                Some(arg) if arg == "subgraph" => {
+                    let mut actors = Vec::new();
+                    while let Some(actor) = args.next_if(|arg| !arg.starts_with("--")) {
+                        actors.push(actor);
+                    }
+                    if actors.is_empty() {
+                        return Err("export subgraph needs at least one actor id or name");
+                    }
+                    Command::Export(ExportScope::Subgraph(actors))
+                }
+                _ => return Err("export takes path, subgraph or nothing to write everything"),
+            },
+            Some(arg) if arg == "co-stars" => match args.next() {
+                Some(actor) if !actor.starts_with("--") => Command::CoStars(actor),
+                _ => return Err("co-stars needs an actor id or name"),
+            },
+            Some(arg) if arg == "shared-movies" || arg == "common-co-stars" => {
+                match (
+                    args.next_if(|arg| !arg.starts_with("--")),
+                    args.next_if(|arg| !arg.starts_with("--")),
+                ) {
+                    (Some(actor_1), Some(actor_2)) if arg == "shared-movies" => {
+                        Command::SharedMovies(actor_1, actor_2)
+                    }
+                    (Some(actor_1), Some(actor_2)) => Command::CommonCoStars(actor_1, actor_2),
+                    _ => {
+                        return Err(
+                            "shared-movies and common-co-stars need two actor ids or names",
+                        );
+                    }
+                }
+            }
+            Some(arg) if arg == "movie-path" => {
+                match (
+                    args.next_if(|arg| !arg.starts_with("--")),
+                    args.next_if(|arg| !arg.starts_with("--")),
+                ) {
+                    (Some(movie_1), Some(movie_2)) => Command::MoviePath(movie_1, movie_2),
+                    _ => return Err("movie-path needs two movie ids or titles"),
+                }
+            }
+            Some(arg) if arg == "actor-movie-path" => {
+                match (
+                    args.next_if(|arg| !arg.starts_with("--")),
+                    args.next_if(|arg| !arg.starts_with("--")),
+                ) {
+                    (Some(actor), Some(movie)) => Command::ActorMoviePath(actor, movie),
+                    _ => {
+                        return Err(
+                            "actor-movie-path needs an actor id or name and a movie id or title",
+                        );
+                    }
+                }
+            }
+            Some(_) => return Err("Unknown command"),
+        };
+
+        let mut targets = Vec::new();
+        let mut format = None;
+        let mut max_sweeps = None;
+        let mut samples = None;
+        let mut threads = None;
+        let mut seed = None;
+        let mut count = None;
+        let mut max_depth = None;
+        let mut max_nodes = None;
+        let mut max_subgraph_nodes = None;
+        let mut hops = None;
+        let mut verbose = false;
+        let mut max_paths = None;
+        let mut exclude_actors = Vec::new();
+        let mut exclude_movies = Vec::new();
+        let mut max_cast_size = None;
+        let mut year_order = None;
+        let mut min_year = None;
+        let mut max_year = None;
+        let mut via_actor = None;
+        let mut via_movie = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--max-paths" => {
+                    max_paths = match args.next().map(|value| value.parse::<usize>()) {
+                        Some(Ok(value)) if value > 0 => Some(value),
+                        _ => return Err("--max-paths needs a positive number"),
+                    };
+                }
+                "--target" => match args.next() {
+                    Some(value) => targets.push(value),
+                    None => return Err("--target needs an actor id or name"),
+                },
+                "--format" => {
+                    format = match args.next().as_deref() {
+                        Some("csv") => Some(OutputFormat::Csv),
+                        Some("json") => Some(OutputFormat::Json),
+                        Some("dot") => Some(OutputFormat::Graph(GraphFormat::Dot)),
+                        Some("graphml") => Some(OutputFormat::Graph(GraphFormat::GraphMl)),
+                        Some("gexf") => Some(OutputFormat::Graph(GraphFormat::Gexf)),
+                        _ => return Err("--format needs csv, json, dot, graphml or gexf"),
+                    };
+                }
+                "--max-sweeps" => {
+                    max_sweeps = match args.next().map(|value| value.parse::<usize>()) {
+                        Some(Ok(value)) if value > 0 => Some(value),
+                        _ => return Err("--max-sweeps needs a positive number"),
+                    };
+                }
+                "--samples" => {
+                    samples = match args.next().map(|value| value.parse::<usize>()) {
+                        Some(Ok(value)) if value > 0 => Some(value),
+                        _ => return Err("--samples needs a positive number"),
+                    };
+                }
+                "--threads" => {
+                    threads = match args.next().map(|value| value.parse::<usize>()) {
+                        Some(Ok(value)) if value > 0 => Some(value),
+                        _ => return Err("--threads needs a positive number"),
+                    };
+                }
+                "--seed" => {
+                    seed = match args.next().map(|value| value.parse::<u64>()) {
+                        Some(Ok(value)) => Some(value),
+                        _ => return Err("--seed needs a number"),
+                    };
+                }
+                "--max-depth" => {
+                    max_depth = match args.next().map(|value| value.parse::<usize>()) {
+                        Some(Ok(value)) => Some(value),
+                        _ => return Err("--max-depth needs a number"),
+                    };
+                }
+                "--max-nodes" => {
+                    max_nodes = match args.next().map(|value| value.parse::<usize>()) {
+                        Some(Ok(value)) if value > 0 => Some(value),
+                        _ => return Err("--max-nodes needs a positive number"),
+                    };
+                }
+                "--max-subgraph-nodes" => {
+                    max_subgraph_nodes = match args.next().map(|value| value.parse::<usize>()) {
+                        Some(Ok(value)) if value > 0 => Some(value),
+                        _ => return Err("--max-subgraph-nodes needs a positive number"),
+                    };
+                }
+                "--hops" => {
+                    hops = match args.next().map(|value| value.parse::<u32>()) {
+                        Some(Ok(value)) => Some(value),
+                        _ => return Err("--hops needs a number"),
+                    };
+                }
+                "--count" => {
+                    count = match args.next().map(|value| value.parse::<usize>()) {
+                        Some(Ok(value)) if value > 0 => Some(value),
+                        _ => return Err("--count needs a positive number"),
+                    };
+                }
+                "--exclude-actor" => match args.next() {
+                    Some(value) => exclude_actors.push(value),
+                    None => return Err("--exclude-actor needs an actor id or name"),
+                },
+                "--exclude-movie" => match args.next() {
+                    Some(value) => exclude_movies.push(value),
+                    None => return Err("--exclude-movie needs a movie id or title"),
+                },
+                "--max-cast-size" => {
+                    max_cast_size = match args.next().map(|value| value.parse::<usize>()) {
+                        Some(Ok(value)) if value > 0 => Some(value),
+                        _ => return Err("--max-cast-size needs a positive number"),
+                    };
+                }
+                "--min-year" => {
+                    min_year = match args.next().map(|value| value.parse::<u32>()) {
+                        Some(Ok(value)) => Some(value),
+                        _ => return Err("--min-year needs a year"),
+                    };
+                }
+                "--max-year" => {
+                    max_year = match args.next().map(|value| value.parse::<u32>()) {
+                        Some(Ok(value)) => Some(value),
+                        _ => return Err("--max-year needs a year"),
+                    };
+                }
+                "--verbose" => verbose = true,
+                "--via" => match args.next() {
+                    Some(value) => via_actor = Some(value),
+                    None => return Err("--via needs an actor id or name"),
+                },
+                "--via-movie" => match args.next() {
+                    Some(value) => via_movie = Some(value),
+                    None => return Err("--via-movie needs a movie id or title"),
+                },
+                "--chronological" => year_order = Some(YearOrder::NonDecreasing),
+                "--strictly-chronological" => year_order = Some(YearOrder::Increasing),
+                _ => return Err("Unknown argument"),
+            }
+        }
+
+        let searches = matches!(
+            command,
+            Command::Path
+                | Command::Nearest
+                | Command::Distances(_)
+                | Command::MoviePath(..)
+                | Command::ActorMoviePath(..)
+                | Command::Export(ExportScope::Path(..))
+        );
+        let restricted = !exclude_actors.is_empty()
+            || !exclude_movies.is_empty()
+            || max_cast_size.is_some()
+            || min_year.is_some()
+            || max_year.is_some();
+        match command {
+            _ if !targets.is_empty() && !matches!(command, Command::Nearest) => {
+                return Err("--target only works with the nearest command");
+            }
+            _ if restricted && !searches => {
+                return Err(
+                    "--exclude-actor, --exclude-movie, --max-cast-size, --min-year and --max-year only work with commands that search",
+                );
+            }
+            Command::Nearest if targets.is_empty() => {
+                return Err("nearest needs at least one --target");
+            }
+            Command::Nearest if max_paths.is_some() || year_order.is_some() => {
+                return Err("nearest only finds one shortest path");
+            }
+            Command::Distances(_) | Command::MoviePath(..) | Command::ActorMoviePath(..)
+                if max_paths.is_some() || year_order.is_some() =>
+            {
+                return Err("distances and movie paths only work with plain shortest paths");
+            }
+            Command::CoStars(_)
+            | Command::Export(_)
+            | Command::SharedMovies(..)
+            | Command::CommonCoStars(..)
+            | Command::Components
+            | Command::Diameter
+            | Command::Centrality
+            | Command::Landmarks
+            | Command::Oracle
+                if max_paths.is_some() || year_order.is_some() =>
+            {
+                return Err("only path searches take --max-paths or a year order");
+            }
+            Command::Distances(_) if matches!(format, Some(OutputFormat::Graph(_))) => {
+                return Err("distances writes csv or json");
+            }
+            Command::Export(_)
+                if matches!(format, Some(OutputFormat::Csv | OutputFormat::Json)) =>
+            {
+                return Err("export writes dot, graphml or gexf");
+            }
+            _ if format.is_some()
+                && !matches!(command, Command::Distances(_) | Command::Export(_)) =>
+            {
+                return Err("--format only works with the distances and export commands");
+            }
+            _ if max_sweeps.is_some() && !matches!(command, Command::Diameter) => {
+                return Err("--max-sweeps only works with the diameter command");
+            }
+            _ if (samples.is_some() || seed.is_some())
+                && !matches!(command, Command::Centrality) =>
+            {
+                return Err("--samples and --seed only work with the centrality command");
+            }
+            _ if threads.is_some()
+                && !matches!(command, Command::Centrality)
+                && (!matches!(command, Command::Path)
+                    || max_paths.is_some()
+                    || year_order.is_some()) =>
+            {
+                return Err("--threads only works with centrality or a plain path search");
+            }
+            Command::Export(ExportScope::Subgraph(_)) if max_depth.is_some() => {
+                return Err("a subgraph export takes --hops rather than --max-depth");
+            }
+            Command::Export(ExportScope::Subgraph(_)) if max_nodes.is_some() => {
+                return Err("a subgraph export takes --max-subgraph-nodes rather than --max-nodes");
+            }
+            _ if (max_depth.is_some() || max_nodes.is_some())
+                && !matches!(
+                    command,
+                    Command::Path | Command::Export(ExportScope::Path(..))
+                ) =>
+            {
+                return Err(
+                    "--max-depth and --max-nodes only work with a path search or a path export",
+                );
+            }
+            _ if verbose && !matches!(command, Command::Path) => {
+                return Err("--verbose only works with a path search");
+            }
+            _ if (via_actor.is_some() || via_movie.is_some())
+                && (!matches!(command, Command::Path)
+                    || max_paths.is_some()
+                    || year_order.is_some()
+                    || max_depth.is_some()
+                    || max_nodes.is_some()
+                    || threads.is_some()) =>
+            {
+                return Err("--via and --via-movie only work with a plain path search");
+            }
+            _ if via_actor.is_some() && via_movie.is_some() => {
+                return Err("--via can't be combined with --via-movie");
+            }
+            _ if (hops.is_some() || max_subgraph_nodes.is_some())
+                && !matches!(command, Command::Export(ExportScope::Subgraph(_))) =>
+            {
+                return Err("--hops and --max-subgraph-nodes only work with a subgraph export");
+            }
+            _ if count.is_some() && !matches!(command, Command::Landmarks) => {
+                return Err("--count only works with the landmarks command");
+            }
+            _ => {}
+        }
+        if year_order.is_some() && max_paths.is_some() {
+            return Err("--max-paths can't be combined with a chronological search");
+        }
+
         // let source_actor_name = match args.next() {
         //     Some(arg) => arg,
         //     None => return Err("Didn't get a source actor name"),
@@ -37,6 +489,28 @@ impl Config {
 
         Ok(Config {
             db_path,
+            command,
+            targets,
+            format,
+            max_sweeps,
+            samples,
+            threads,
+            seed,
+            count,
+            max_depth,
+            max_nodes,
+            max_subgraph_nodes,
+            hops,
+            verbose,
+            max_paths,
+            exclude_actors,
+            exclude_movies,
+            max_cast_size,
+            year_order,
+            min_year,
+            max_year,
+            via_actor,
+            via_movie,
             // source_actor_name,
             // target_actor_name,
         })
@@ -57,15 +531,166 @@ async fn main() {
         );
     });
 
+    let mut options = SearchOptions {
+        max_cast_size: config.max_cast_size,
+        min_year: config.min_year,
+        max_year: config.max_year,
+        ..Default::default()
+    };
+    for query in &config.exclude_actors {
+        let ids = interactive_io::find_actor_ids(&actors, query);
+        if ids.is_empty() {
+            println!("No actor matches {:?}", query);
+            return;
+        }
+        options.excluded_actors.extend(ids);
+    }
+
+    let mut targets = Vec::new();
+    for query in &config.targets {
+        let ids = interactive_io::find_actor_ids(&actors, query);
+        if ids.is_empty() {
+            println!("No actor matches {:?}", query);
+            return;
+        }
+        targets.extend(ids);
+    }
+    // search in a stable order, so ties always go the same way
+    targets.sort_unstable();
+    targets.dedup();
+
     // spawn threads to read movies and connections
+    let db_path = config.db_path.clone();
     let movie_conns_handler =
-        thread::spawn(move || MovieDBBuilder::build_movies_connections(&config.db_path));
+        thread::spawn(move || MovieDBBuilder::build_movies_connections(&db_path));
+
+    match &config.command {
+        Command::MoviePath(movie_1, movie_2) => {
+            run_movie_path(
+                movie_1,
+                movie_2,
+                movie_conns_handler,
+                actors,
+                &config,
+                options,
+            )
+            .await;
+            return;
+        }
+        Command::ActorMoviePath(actor, movie) => {
+            run_actor_movie_path(actor, movie, movie_conns_handler, actors, &config, options).await;
+            return;
+        }
+        Command::Export(scope) => {
+            run_export(scope, movie_conns_handler, actors, &config, options).await;
+            return;
+        }
+        Command::CoStars(actor) => {
+            run_co_stars(actor, movie_conns_handler, actors, &config, options);
+            return;
+        }
+        Command::SharedMovies(actor_1, actor_2) => {
+            run_shared_movies(
+                actor_1,
+                actor_2,
+                movie_conns_handler,
+                actors,
+                &config,
+                options,
+            );
+            return;
+        }
+        Command::CommonCoStars(actor_1, actor_2) => {
+            run_common_co_stars(
+                actor_1,
+                actor_2,
+                movie_conns_handler,
+                actors,
+                &config,
+                options,
+            );
+            return;
+        }
+        _ => {}
+    }
+
+    if let Command::Components = config.command {
+        let num_actors = actors.len();
+        let Some(db) = join_movie_db(movie_conns_handler, actors, &config, &mut options) else {
+            return;
+        };
+        print_components(num_actors, &db);
+        return;
+    }
+
+    if let Command::Diameter = config.command {
+        let Some(db) = join_movie_db(movie_conns_handler, actors, &config, &mut options) else {
+            return;
+        };
+        print_diameter(config.max_sweeps.unwrap_or(DEFAULT_MAX_SWEEPS), &db);
+        return;
+    }
+
+    if let Command::Centrality = config.command {
+        let Some(db) = join_movie_db(movie_conns_handler, actors, &config, &mut options) else {
+            return;
+        };
+        let mut centrality_options = CentralityOptions {
+            num_samples: config.samples,
+            ..Default::default()
+        };
+        if let Some(threads) = config.threads {
+            centrality_options.num_threads = threads;
+        }
+        if let Some(seed) = config.seed {
+            centrality_options.seed = seed;
+        }
+        write_centrality(&centrality_options, &db);
+        return;
+    }
+
+    if let Command::Landmarks = config.command {
+        let Some(db) = join_movie_db(movie_conns_handler, actors, &config, &mut options) else {
+            return;
+        };
+        save_landmarks(config.count.unwrap_or(DEFAULT_LANDMARKS), &config, &db);
+        return;
+    }
+
+    if let Command::Oracle = config.command {
+        let Some(db) = join_movie_db(movie_conns_handler, actors, &config, &mut options) else {
+            return;
+        };
+        save_oracle(&config, &db);
+        return;
+    }
+
+    if let Command::Distances(query) = &config.command {
+        let Some(source_actor) = find_unique_actor(&actors, query) else {
+            return;
+        };
+        let Some(db) = join_movie_db(movie_conns_handler, actors, &config, &mut options) else {
+            return;
+        };
+        let format = config.format.as_ref().unwrap_or(&OutputFormat::Csv);
+        write_distances(source_actor, format, &db, &options).await;
+        return;
+    }
 
     // get source and target actors
     println!("{}", ["#"; 20].concat());
     println!("Enter source actor name: ");
     let source_actor = interactive_io::get_unique_actor(stdin().lock(), stdout(), &actors);
 
+    if let Command::Nearest = config.command {
+        let db = join_movie_db(movie_conns_handler, actors, &config, &mut options);
+        let Some(db) = db else {
+            return;
+        };
+        print_nearest(source_actor, &targets, &db, &options).await;
+        return;
+    }
+
     println!("{}", ["#"; 20].concat());
     println!("Enter target actor name: ");
     let target_actor = interactive_io::get_unique_actor(stdin().lock(), stdout(), &actors);
@@ -76,43 +701,797 @@ async fn main() {
         return;
     }
 
-    // join handles
-    let (movies, actor_to_movies, movie_to_actors) = movie_conns_handler.join().unwrap().unwrap();
-    // make db and return
-    let db = MovieDB {
-        actor_to_movies,
-        movie_to_actors,
-        actors,
-        movies,
+    let via_actor = match &config.via_actor {
+        Some(query) => match find_unique_actor(&actors, query) {
+            Some(actor_id) => Some(Via::Actor(actor_id)),
+            None => return,
+        },
+        None => None,
+    };
+
+    let Some(db) = join_movie_db(movie_conns_handler, actors, &config, &mut options) else {
+        return;
+    };
+    let via = match &config.via_movie {
+        Some(query) => match find_unique_movie(&db.movies, query) {
+            Some(movie_id) => Some(Via::Movie(movie_id)),
+            None => return,
+        },
+        None => via_actor,
     };
 
     // get shortest path
     println!("{}", ["#"; 20].concat());
     println!("Calculating shortest path...");
     println!("{}", ["#"; 20].concat());
-    let shortest_path = shortest_path(source_actor, target_actor, &db).await;
+    let limits = SearchLimits {
+        max_depth: config.max_depth,
+        max_nodes_explored: config.max_nodes,
+        ..Default::default()
+    };
+    let shortest_paths = match (config.max_paths, config.year_order, via) {
+        (None, None, Some(via)) => {
+            via_path(source_actor, target_actor, via, &db, &options, &limits)
+                .await
+                .map(|path| vec![path])
+        }
+        (None, Some(year_order), _) => chronological_path(
+            source_actor,
+            target_actor,
+            year_order,
+            &db,
+            &options,
+            &limits,
+        )
+        .await
+        .map(|path| vec![path]),
+        (None, None, None) => {
+            // the indices know nothing of the limits, and asking for threads
+            // asks for the parallel BFS
+            let limited = limits.max_depth.is_some()
+                || limits.max_nodes_explored.is_some()
+                || config.threads.is_some();
+            let oracle = if options.is_unrestricted() && !limited {
+                load_oracle(&config, &db)
+            } else {
+                None
+            };
+            let landmarks = if limited {
+                None
+            } else {
+                load_landmarks(&config, &db)
+            };
+            // searches for pairs the oracle can't answer fall through to a BFS
+            let oracle_path =
+                oracle.and_then(|oracle| oracle.path(source_actor, target_actor, &db));
+            let path = if let Some(path) = oracle_path {
+                if config.verbose {
+                    eprintln!("answered by the distance oracle, no search stats");
+                }
+                Ok(path)
+            } else if let Some(landmarks) = landmarks {
+                if config.verbose {
+                    eprintln!("searched with landmarks, no search stats");
+                }
+                landmark_path(source_actor, target_actor, &landmarks, &db, &options).await
+            } else {
+                let result = match config.threads {
+                    Some(num_threads) => {
+                        let parallel = ParallelOptions::new(num_threads)
+                            .expect("Failed to start the search threads");
+                        parallel_shortest_path(
+                            source_actor,
+                            target_actor,
+                            &db,
+                            &options,
+                            &limits,
+                            &parallel,
+                        )
+                    }
+                    None => shortest_path(source_actor, target_actor, &db, &options, &limits).await,
+                };
+                result.map(|(path, stats)| {
+                    if config.verbose {
+                        eprintln!("{}", stats);
+                    }
+                    path
+                })
+            };
+            path.map(|path| vec![path])
+        }
+        (Some(max_paths), _, _) => {
+            all_shortest_paths(
+                source_actor,
+                target_actor,
+                Some(max_paths),
+                &db,
+                &options,
+                &limits,
+            )
+            .await
+        }
+    };
 
-    match shortest_path {
-        Ok(path) => {
-            println!("Degrees of connection: {}", path.len() - 1);
-            println!("Shortest path is: ");
+    match shortest_paths {
+        Ok(paths) => {
+            println!("Degrees of connection: {}", paths[0].len() - 1);
+            for (path_index, path) in paths.iter().enumerate() {
+                if paths.len() == 1 {
+                    println!("Shortest path is: ");
+                } else {
+                    println!("Shortest path {} of {}: ", path_index + 1, paths.len());
+                }
+                print_path(path, &db);
+            }
+        }
+        Err(err) => {
+            println!("No path found");
+            println!("{}", err);
+        }
+    }
+}
+
+/// The id of the only actor matching `query`, or `None` after listing the
+/// matches when there isn't exactly one.
+fn find_unique_actor(actors: &HashMap<usize, Actor>, query: &str) -> Option<usize> {
+    let ids = interactive_io::find_actor_ids(actors, query);
+    match ids.len() {
+        1 => ids.into_iter().next(),
+        0 => {
+            eprintln!("No actor matches {:?}", query);
+            None
+        }
+        _ => {
+            eprintln!("{:?} matches several actors, pick one by id:", query);
+            for id in ids {
+                eprintln!("{}", actors[&id]);
+            }
+            None
+        }
+    }
+}
 
-            for node_index in 0..(path.len() - 1) {
-                let actor_1 = db.actors.get(&path[node_index].actor_id).unwrap();
-                let actor_2 = db.actors.get(&path[node_index + 1].actor_id).unwrap();
-                let movie = db
-                    .movies
-                    .get(&path[node_index + 1].movie_id.unwrap())
-                    .unwrap();
-                println!(
-                    "{} was in {} with {}",
-                    actor_1.full_name, movie.title, actor_2.full_name
-                )
+/// The same for movies.
+fn find_unique_movie(movies: &HashMap<usize, Movie>, query: &str) -> Option<usize> {
+    let ids = interactive_io::find_movie_ids(movies, query);
+    match ids.len() {
+        1 => ids.into_iter().next(),
+        0 => {
+            eprintln!("No movie matches {:?}", query);
+            None
+        }
+        _ => {
+            eprintln!("{:?} matches several movies, pick one by id:", query);
+            for id in ids {
+                let movie = &movies[&id];
+                eprintln!("(id: {}, title: {}, year: {})", id, movie.title, movie.year);
             }
+            None
+        }
+    }
+}
+
+/// Waits for the movies and connections to load, resolves the excluded
+/// movies into `options`, and assembles the db. Returns `None` if an excluded
+/// movie doesn't exist.
+fn join_movie_db(
+    movie_conns_handler: MovieConnsHandle,
+    actors: HashMap<usize, Actor>,
+    config: &Config,
+    options: &mut SearchOptions,
+) -> Option<MovieDB> {
+    let (movies, connections) = movie_conns_handler.join().unwrap().unwrap();
+    eprintln!("{}", connections.memory_summary());
+    for query in &config.exclude_movies {
+        let ids = interactive_io::find_movie_ids(&movies, query);
+        if ids.is_empty() {
+            println!("No movie matches {:?}", query);
+            return None;
         }
+        options.excluded_movies.extend(ids);
+    }
+    Some(MovieDB {
+        connections,
+        actors,
+        movies,
+    })
+}
+
+/// Runs `movie-path`: prints the shortest chain between two movies.
+async fn run_movie_path(
+    movie_1: &str,
+    movie_2: &str,
+    movie_conns_handler: MovieConnsHandle,
+    actors: HashMap<usize, Actor>,
+    config: &Config,
+    mut options: SearchOptions,
+) {
+    let Some(db) = join_movie_db(movie_conns_handler, actors, config, &mut options) else {
+        return;
+    };
+    let (Some(movie_1), Some(movie_2)) = (
+        find_unique_movie(&db.movies, movie_1),
+        find_unique_movie(&db.movies, movie_2),
+    ) else {
+        return;
+    };
+    print_endpoint_path(
+        Endpoint::Movie(movie_1),
+        Endpoint::Movie(movie_2),
+        &db,
+        &options,
+    )
+    .await;
+}
+
+/// Runs `actor-movie-path`: prints the shortest chain from an actor to a
+/// movie.
+async fn run_actor_movie_path(
+    actor: &str,
+    movie: &str,
+    movie_conns_handler: MovieConnsHandle,
+    actors: HashMap<usize, Actor>,
+    config: &Config,
+    mut options: SearchOptions,
+) {
+    let Some(actor) = find_unique_actor(&actors, actor) else {
+        return;
+    };
+    let Some(db) = join_movie_db(movie_conns_handler, actors, config, &mut options) else {
+        return;
+    };
+    let Some(movie) = find_unique_movie(&db.movies, movie) else {
+        return;
+    };
+    print_endpoint_path(
+        Endpoint::Actor(actor),
+        Endpoint::Movie(movie),
+        &db,
+        &options,
+    )
+    .await;
+}
+
+/// Prints the shortest chain between two endpoints, starting and ending
+/// with the movies among them.
+async fn print_endpoint_path(
+    source: Endpoint,
+    target: Endpoint,
+    db: &MovieDB,
+    options: &SearchOptions,
+) {
+    let found = endpoint_path(source, target, db, options, &SearchLimits::default()).await;
+    let found = match found {
+        Ok(found) => found,
         Err(err) => {
             println!("No path found");
             println!("{}", err);
+            return;
+        }
+    };
+    println!("Degrees of connection: {}", found.num_hops());
+    let Some(first) = found.path.first() else {
+        // both ends are the same movie
+        return;
+    };
+    let was_in = |actor_id: usize, movie_id: usize| {
+        let movie = &db.movies[&movie_id];
+        println!(
+            "{} was in {} ({})",
+            db.actors[&actor_id].full_name, movie.title, movie.year
+        );
+    };
+    if let Some(movie_id) = found.source_movie {
+        was_in(first.actor_id, movie_id);
+    }
+    print_path(&found.path, db);
+    if let Some(movie_id) = found.target_movie {
+        was_in(found.path.last().unwrap().actor_id, movie_id);
+    }
+}
+
+async fn print_nearest(
+    source_actor: usize,
+    targets: &[usize],
+    db: &MovieDB,
+    options: &SearchOptions,
+) {
+    println!("{}", ["#"; 20].concat());
+    println!("Looking for the nearest of {} actors...", targets.len());
+    println!("{}", ["#"; 20].concat());
+    match nearest_actor(source_actor, targets, db, options).await {
+        Ok((nearest, path)) => {
+            println!("Nearest actor is: {}", db.actors[&nearest].full_name);
+            println!("Degrees of connection: {}", path.len() - 1);
+            print_path(&path, db);
+        }
+        Err(err) => {
+            println!("No path found");
+            println!("{}", err);
+        }
+    }
+}
+
+/// Runs `export`: writes the graph, a path or a subgraph to stdout.
+async fn run_export(
+    scope: &ExportScope,
+    movie_conns_handler: MovieConnsHandle,
+    actors: HashMap<usize, Actor>,
+    config: &Config,
+    mut options: SearchOptions,
+) {
+    let format = match config.format {
+        Some(OutputFormat::Graph(format)) => format,
+        _ => GraphFormat::Dot,
+    };
+    let (db, graph) = match scope {
+        ExportScope::All => {
+            let Some(db) = join_movie_db(movie_conns_handler, actors, config, &mut options) else {
+                return;
+            };
+            let graph = ExportGraph::whole(&db);
+            (db, graph)
+        }
+        ExportScope::Path(actor_1, actor_2) => {
+            let (Some(actor_1), Some(actor_2)) = (
+                find_unique_actor(&actors, actor_1),
+                find_unique_actor(&actors, actor_2),
+            ) else {
+                return;
+            };
+            let Some(db) = join_movie_db(movie_conns_handler, actors, config, &mut options) else {
+                return;
+            };
+            let limits = SearchLimits {
+                max_depth: config.max_depth,
+                max_nodes_explored: config.max_nodes,
+                ..Default::default()
+            };
+            let path = match shortest_path(actor_1, actor_2, &db, &options, &limits).await {
+                Ok((path, _)) => path,
+                Err(err) => {
+                    eprintln!("No path found");
+                    eprintln!("{}", err.message());
+                    return;
+                }
+            };
+            let graph = ExportGraph::from_path(&path);
+            (db, graph)
+        }
+        ExportScope::Subgraph(queries) => {
+            let mut actor_ids = Vec::new();
+            for query in queries {
+                let Some(actor_id) = find_unique_actor(&actors, query) else {
+                    return;
+                };
+                actor_ids.push(actor_id);
+            }
+            let Some(db) = join_movie_db(movie_conns_handler, actors, config, &mut options) else {
+                return;
+            };
+            let hops = config.hops.unwrap_or(DEFAULT_HOPS);
+            let max_nodes = config.max_subgraph_nodes.unwrap_or(DEFAULT_SUBGRAPH_NODES);
+            let subgraph = match db.subgraph(&actor_ids, hops, max_nodes) {
+                Ok(subgraph) => subgraph,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return;
+                }
+            };
+            let graph = ExportGraph::from_subgraph(&subgraph);
+            (db, graph)
+        }
+    };
+    eprintln!(
+        "Writing {} actors, {} movies and {} credits",
+        graph.actor_ids.len(),
+        graph.movie_ids.len(),
+        graph.credits.len()
+    );
+    let mut out = BufWriter::new(stdout().lock());
+    if let Err(err) = write_graph(&graph, format, &db, &mut out).and_then(|()| out.flush()) {
+        eprintln!("Problem writing the graph: {}", err);
+        process::exit(1);
+    }
+}
+
+/// Runs `co-stars`.
+fn run_co_stars(
+    actor: &str,
+    movie_conns_handler: MovieConnsHandle,
+    actors: HashMap<usize, Actor>,
+    config: &Config,
+    mut options: SearchOptions,
+) {
+    let Some(actor) = find_unique_actor(&actors, actor) else {
+        return;
+    };
+    let Some(db) = join_movie_db(movie_conns_handler, actors, config, &mut options) else {
+        return;
+    };
+    print_co_stars(actor, &db);
+}
+
+/// Runs `shared-movies`.
+fn run_shared_movies(
+    actor_1: &str,
+    actor_2: &str,
+    movie_conns_handler: MovieConnsHandle,
+    actors: HashMap<usize, Actor>,
+    config: &Config,
+    mut options: SearchOptions,
+) {
+    let (Some(actor_1), Some(actor_2)) = (
+        find_unique_actor(&actors, actor_1),
+        find_unique_actor(&actors, actor_2),
+    ) else {
+        return;
+    };
+    let Some(db) = join_movie_db(movie_conns_handler, actors, config, &mut options) else {
+        return;
+    };
+    print_shared_movies(actor_1, actor_2, &db);
+}
+
+/// Runs `common-co-stars`.
+fn run_common_co_stars(
+    actor_1: &str,
+    actor_2: &str,
+    movie_conns_handler: MovieConnsHandle,
+    actors: HashMap<usize, Actor>,
+    config: &Config,
+    mut options: SearchOptions,
+) {
+    let (Some(actor_1), Some(actor_2)) = (
+        find_unique_actor(&actors, actor_1),
+        find_unique_actor(&actors, actor_2),
+    ) else {
+        return;
+    };
+    let Some(db) = join_movie_db(movie_conns_handler, actors, config, &mut options) else {
+        return;
+    };
+    print_common_co_stars(actor_1, actor_2, &db);
+}
+
+/// Prints the co-stars of the actor, those they made the most movies with
+/// first.
+fn print_co_stars(actor: usize, db: &MovieDB) {
+    let co_stars = query::co_stars(db, actor);
+    println!(
+        "{} was in movies with {} actors",
+        db.actors[&actor].full_name,
+        co_stars.len()
+    );
+    for co_star in co_stars {
+        println!(
+            "{} ({}): {} shared movies",
+            db.actors[&co_star.actor_id].full_name, co_star.actor_id, co_star.num_shared_movies
+        );
+    }
+}
+
+/// Prints the movies both actors were in, oldest first.
+fn print_shared_movies(actor_1: usize, actor_2: usize, db: &MovieDB) {
+    let movies = query::shared_movies(db, actor_1, actor_2);
+    println!(
+        "{} and {} were in {} movies together",
+        db.actors[&actor_1].full_name,
+        db.actors[&actor_2].full_name,
+        movies.len()
+    );
+    for movie_id in movies {
+        let movie = &db.movies[&movie_id];
+        println!("{} ({}, {})", movie.title, movie.year, movie_id);
+    }
+}
+
+/// Prints the actors who were in a movie with each of the two actors.
+fn print_common_co_stars(actor_1: usize, actor_2: usize, db: &MovieDB) {
+    let common = query::common_co_stars(db, actor_1, actor_2);
+    let name_1 = &db.actors[&actor_1].full_name;
+    let name_2 = &db.actors[&actor_2].full_name;
+    println!(
+        "{} actors were in movies with both {} and {}",
+        common.len(),
+        name_1,
+        name_2
+    );
+    for co_star in common {
+        println!(
+            "{} ({}): {} movies with {}, {} with {}",
+            db.actors[&co_star.actor_id].full_name,
+            co_star.actor_id,
+            co_star.num_movies_with_1,
+            name_1,
+            co_star.num_movies_with_2,
+            name_2
+        );
+    }
+}
+
+/// Prints how many connected components there are and how big they are.
+fn print_components(num_actors: usize, db: &MovieDB) {
+    let connections = &db.connections;
+    let components = connections.components();
+    println!(
+        "{} actors with credits in {} connected components",
+        connections.num_actors(),
+        components.num_components()
+    );
+    println!(
+        "{} actors have no credits",
+        num_actors.saturating_sub(connections.num_actors())
+    );
+    if let Some(&largest) = components.sizes().first() {
+        println!(
+            "Largest component: {} actors ({:.1}%)",
+            largest,
+            100.0 * largest as f64 / connections.num_actors() as f64
+        );
+    }
+
+    println!("{}", ["#"; 20].concat());
+    println!("Component sizes:");
+    // sizes come largest first, so equal sizes are next to each other
+    let sizes = components.sizes();
+    let mut start = 0;
+    while start < sizes.len() {
+        let size = sizes[start];
+        let count = sizes[start..]
+            .iter()
+            .take_while(|&&other| other == size)
+            .count();
+        println!("{} actors: {} component(s)", size, count);
+        start += count;
+    }
+}
+
+/// Prints the diameter and radius of the largest component, with the actor
+/// pairs that are farthest apart and a path between the first pair.
+fn print_diameter(max_sweeps: usize, db: &MovieDB) {
+    let Some(estimate) = analysis::estimate_diameter(db, 0, max_sweeps) else {
+        println!("No credits to measure");
+        return;
+    };
+    println!(
+        "Largest component: {} actors, {} BFS sweeps",
+        estimate.num_actors, estimate.num_sweeps
+    );
+    if estimate.is_exact() {
+        println!("Diameter: {}", estimate.diameter);
+        println!("Radius: {}", estimate.radius);
+    } else {
+        println!(
+            "Diameter: between {} and {}",
+            estimate.diameter, estimate.diameter_upper_bound
+        );
+        println!(
+            "Radius: between {} and {}",
+            estimate.radius_lower_bound, estimate.radius
+        );
+    }
+
+    let name = |actor_id| {
+        db.actors
+            .get(&actor_id)
+            .map_or(actor_id.to_string(), |actor: &Actor| {
+                actor.full_name.clone()
+            })
+    };
+    println!("{}", ["#"; 20].concat());
+    println!("Actors {} hops apart:", estimate.diameter);
+    for &(actor_1, actor_2) in &estimate.peripheral_pairs {
+        println!("{} and {}", name(actor_1), name(actor_2));
+    }
+    println!("{}", ["#"; 20].concat());
+    println!("Longest shortest path found:");
+    print_path(&estimate.diameter_path, db);
+}
+
+/// Picks `count` landmarks, runs a BFS from each and saves the distances
+/// next to the dataset, where path searches pick them up.
+fn save_landmarks(count: usize, config: &Config, db: &MovieDB) {
+    let landmarks = Landmarks::select(db, count);
+    let path = config.db_path.join(LANDMARKS_FILE);
+    match landmarks.save(&path, &db.connections) {
+        Ok(()) => println!(
+            "Saved {} landmarks to {:?} ({:.1} MiB)",
+            landmarks.landmark_ids().len(),
+            path,
+            landmarks.heap_size() as f64 / (1024.0 * 1024.0)
+        ),
+        Err(err) => eprintln!("Problem saving landmarks to {:?}: {}", path, err),
+    }
+}
+
+/// Builds the distance oracle and saves it next to the dataset, where path
+/// searches and the webapp pick it up.
+fn save_oracle(config: &Config, db: &MovieDB) {
+    let start = Instant::now();
+    let oracle = DistanceOracle::build(db);
+    println!(
+        "Built labels with {} entries, {:.1} per actor, in {:.1?}",
+        oracle.num_entries(),
+        oracle.num_entries() as f64 / db.connections.num_actors().max(1) as f64,
+        start.elapsed()
+    );
+    let path = config.db_path.join(ORACLE_FILE);
+    match oracle.save(&path, &db.connections) {
+        Ok(()) => println!(
+            "Saved the distance oracle to {:?} ({:.1} MiB)",
+            path,
+            oracle.heap_size() as f64 / (1024.0 * 1024.0)
+        ),
+        Err(err) => eprintln!("Problem saving the distance oracle to {:?}: {}", path, err),
+    }
+}
+
+/// Distance oracle saved next to the dataset by the `oracle` command, if
+/// there is one and it matches the dataset.
+fn load_oracle(config: &Config, db: &MovieDB) -> Option<DistanceOracle> {
+    let path = config.db_path.join(ORACLE_FILE);
+    if !path.exists() {
+        return None;
+    }
+    match DistanceOracle::load(&path, &db.connections) {
+        Ok(oracle) => {
+            eprintln!("Searching with the distance oracle");
+            Some(oracle)
         }
+        Err(err) => {
+            eprintln!("Ignoring the distance oracle in {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+/// Landmarks saved next to the dataset by the `landmarks` command, if there
+/// are any and they match it.
+fn load_landmarks(config: &Config, db: &MovieDB) -> Option<Landmarks> {
+    let path = config.db_path.join(LANDMARKS_FILE);
+    if !path.exists() {
+        return None;
+    }
+    match Landmarks::load(&path, &db.connections) {
+        Ok(landmarks) => {
+            eprintln!(
+                "Searching with {} landmarks",
+                landmarks.landmark_ids().len()
+            );
+            Some(landmarks)
+        }
+        Err(err) => {
+            eprintln!("Ignoring landmarks in {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CentralityRow<'a> {
+    actor_id: usize,
+    full_name: &'a str,
+    closeness: f64,
+    betweenness: f64,
+}
+
+/// Writes the closeness and betweenness of every actor with credits to
+/// stdout as CSV, most central first, ready for `scripts/load_centrality.sh`.
+fn write_centrality(options: &CentralityOptions, db: &MovieDB) {
+    let sources = options
+        .num_samples
+        .map_or("every actor".to_string(), |samples| {
+            format!("{} sampled actors", samples)
+        });
+    eprintln!(
+        "Running a BFS from {} on {} threads...",
+        sources, options.num_threads
+    );
+    let mut rows: Vec<CentralityRow> = analysis::centrality(db, options)
+        .into_iter()
+        .map(|row| CentralityRow {
+            actor_id: row.actor_id,
+            full_name: db
+                .actors
+                .get(&row.actor_id)
+                .map_or("", |actor| actor.full_name.as_str()),
+            closeness: row.closeness,
+            betweenness: row.betweenness,
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.closeness
+            .total_cmp(&a.closeness)
+            .then(a.actor_id.cmp(&b.actor_id))
+    });
+
+    let mut writer = csv::Writer::from_writer(stdout());
+    for row in &rows {
+        writer.serialize(row).expect("Failed to write CSV row");
+    }
+    writer.flush().expect("Failed to write CSV");
+}
+
+#[derive(Serialize)]
+struct DistanceRow<'a> {
+    actor_id: usize,
+    full_name: &'a str,
+    distance: u32,
+}
+
+#[derive(Serialize)]
+struct DistanceTable<'a> {
+    actor_id: usize,
+    histogram: &'a [usize],
+    distances: Vec<DistanceRow<'a>>,
+}
+
+/// Writes the distance from `source_actor` to every actor it reaches to
+/// stdout, nearest first, and the number of actors at each distance to
+/// stderr.
+async fn write_distances(
+    source_actor: usize,
+    format: &OutputFormat,
+    db: &MovieDB,
+    options: &SearchOptions,
+) {
+    let profile = match distance_profile(source_actor, db, options).await {
+        Ok(profile) => profile,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let mut rows: Vec<DistanceRow> = profile
+        .distances
+        .iter()
+        .map(|(&actor_id, &distance)| DistanceRow {
+            actor_id,
+            full_name: db
+                .actors
+                .get(&actor_id)
+                .map_or("", |actor| actor.full_name.as_str()),
+            distance,
+        })
+        .collect();
+    rows.sort_unstable_by_key(|row| (row.distance, row.actor_id));
+
+    match format {
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(stdout());
+            for row in &rows {
+                writer.serialize(row).expect("Failed to write CSV row");
+            }
+            writer.flush().expect("Failed to write CSV");
+        }
+        OutputFormat::Json => {
+            let table = DistanceTable {
+                actor_id: source_actor,
+                histogram: &profile.histogram,
+                distances: rows,
+            };
+            serde_json::to_writer_pretty(stdout(), &table).expect("Failed to write JSON");
+            println!();
+        }
+        OutputFormat::Graph(_) => unreachable!("distances only writes csv or json"),
+    }
+    for (distance, count) in profile.histogram.iter().enumerate() {
+        eprintln!("Actors at distance {}: {}", distance, count);
+    }
+}
+
+fn print_path(path: &[Node], db: &MovieDB) {
+    for node_index in 0..(path.len() - 1) {
+        let actor_1 = db.actors.get(&path[node_index].actor_id).unwrap();
+        let actor_2 = db.actors.get(&path[node_index + 1].actor_id).unwrap();
+        let movie = db
+            .movies
+            .get(&path[node_index + 1].movie_id.unwrap())
+            .unwrap();
+        println!(
+            "{} was in {} ({}) with {}",
+            actor_1.full_name, movie.title, movie.year, actor_2.full_name
+        )
     }
 }
@@ -18,6 +18,14 @@ pub struct ApplicationSettings {
     pub port: u16,
     pub host: String,
     pub allow_cors: bool,
+    /// Threads a shortest path search may use. With 1, searches run on the
+    /// task of their request.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub search_threads: usize,
+    /// Shortest paths kept for repeated searches. With 0, every search
+    /// runs from scratch.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub path_cache_size: usize,
 }
 
 #[derive(Deserialize, Clone)]
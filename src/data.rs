@@ -10,6 +10,14 @@ use std::path::{Path, PathBuf};
 use std::thread;
 
 use std::hash::{Hash, Hasher};
+
+mod components;
+mod subgraph;
+pub mod synthetic;
+
+pub use components::{ComponentId, Components};
+pub use subgraph::Subgraph;
+
 // movie struct
 #[derive(Debug, Deserialize)]
 pub struct Movie {
@@ -25,15 +33,288 @@ pub struct Actor {
     // pub connectivity: Option<usize>,
 }
 
+/// Dense index of an actor in [`Connections`].
+pub type ActorIndex = u32;
+/// Dense index of a movie in [`Connections`].
+pub type MovieIndex = u32;
+
 type Mapping = HashMap<usize, HashSet<usize>>;
 
 pub struct MovieDB {
-    pub actor_to_movies: HashMap<usize, HashSet<usize>>,
-    pub movie_to_actors: HashMap<usize, HashSet<usize>>,
+    pub connections: Connections,
     pub actors: HashMap<usize, Actor>,
     pub movies: HashMap<usize, Movie>,
 }
 
+/// Actor–movie credits in compressed sparse row (CSR) form.
+///
+/// Actor and movie ids are remapped to dense `u32` indices in ascending id
+/// order. The movies of actor `a` are
+/// `actor_movies[actor_offsets[a]..actor_offsets[a + 1]]`, and the cast of
+/// movie `m` is stored the same way in `movie_offsets` / `movie_actors`.
+pub struct Connections {
+    actor_ids: Vec<usize>,
+    movie_ids: Vec<usize>,
+    actor_offsets: Vec<u32>,
+    actor_movies: Vec<MovieIndex>,
+    movie_offsets: Vec<u32>,
+    movie_actors: Vec<ActorIndex>,
+    components: Components,
+}
+
+impl MovieDB {
+    /// Ids of the movies the actor was credited in.
+    pub fn movies_of_actor(&self, actor_id: usize) -> impl Iterator<Item = usize> + '_ {
+        let connections = &self.connections;
+        connections
+            .actor_index(actor_id)
+            .into_iter()
+            .flat_map(|actor_index| connections.movies_of(actor_index))
+            .map(|&movie_index| connections.movie_id(movie_index))
+    }
+
+    /// Ids of the actors credited in the movie.
+    pub fn actors_of_movie(&self, movie_id: usize) -> impl Iterator<Item = usize> + '_ {
+        let connections = &self.connections;
+        connections
+            .movie_index(movie_id)
+            .into_iter()
+            .flat_map(|movie_index| connections.cast_of(movie_index))
+            .map(|&actor_index| connections.actor_id(actor_index))
+    }
+
+    /// The movies of every actor with credits, keyed by actor id. Builds the
+    /// map from the connections, so prefer `movies_of_actor` for lookups.
+    pub fn actor_to_movies(&self) -> Mapping {
+        let connections = &self.connections;
+        (0..connections.num_actors() as ActorIndex)
+            .map(|actor_index| {
+                let movies = connections.movies_of(actor_index);
+                let movie_ids = movies.iter().map(|&movie| connections.movie_id(movie));
+                (connections.actor_id(actor_index), movie_ids.collect())
+            })
+            .collect()
+    }
+
+    /// The cast of every movie, keyed by movie id. Builds the map from the
+    /// connections, so prefer `actors_of_movie` for lookups.
+    pub fn movie_to_actors(&self) -> Mapping {
+        let connections = &self.connections;
+        (0..connections.num_movies() as MovieIndex)
+            .map(|movie_index| {
+                let cast = connections.cast_of(movie_index);
+                let actor_ids = cast.iter().map(|&actor| connections.actor_id(actor));
+                (connections.movie_id(movie_index), actor_ids.collect())
+            })
+            .collect()
+    }
+
+    /// Whether some chain of co-stars links the two actors. An actor without
+    /// credits is only connected to themselves.
+    pub fn connected(&self, actor_id_1: usize, actor_id_2: usize) -> bool {
+        if actor_id_1 == actor_id_2 {
+            return true;
+        }
+        let connections = &self.connections;
+        match (
+            connections.actor_index(actor_id_1),
+            connections.actor_index(actor_id_2),
+        ) {
+            (Some(actor_1), Some(actor_2)) => {
+                let components = connections.components();
+                components.component_of(actor_1) == components.component_of(actor_2)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Connections {
+    /// Builds the index from (actor id, movie id) pairs. Duplicate pairs are
+    /// ignored.
+    pub fn from_pairs(mut pairs: Vec<(usize, usize)>) -> Connections {
+        pairs.sort_unstable();
+        pairs.dedup();
+
+        // pairs are sorted by actor, so actor ids come out sorted
+        let mut actor_ids: Vec<usize> = pairs.iter().map(|(actor_id, _)| *actor_id).collect();
+        actor_ids.dedup();
+        let mut movie_ids: Vec<usize> = pairs.iter().map(|(_, movie_id)| *movie_id).collect();
+        movie_ids.sort_unstable();
+        movie_ids.dedup();
+        actor_ids.shrink_to_fit();
+        movie_ids.shrink_to_fit();
+
+        let mut actor_offsets = vec![0u32; actor_ids.len() + 1];
+        let mut movie_offsets = vec![0u32; movie_ids.len() + 1];
+        let mut dense_pairs = Vec::with_capacity(pairs.len());
+        let mut actor_index = 0;
+        for (actor_id, movie_id) in pairs {
+            while actor_ids[actor_index] != actor_id {
+                actor_index += 1;
+            }
+            let movie_index = movie_ids.binary_search(&movie_id).unwrap();
+            actor_offsets[actor_index + 1] += 1;
+            movie_offsets[movie_index + 1] += 1;
+            dense_pairs.push((actor_index as ActorIndex, movie_index as MovieIndex));
+        }
+        for offsets in [&mut actor_offsets, &mut movie_offsets] {
+            for i in 1..offsets.len() {
+                offsets[i] += offsets[i - 1];
+            }
+        }
+
+        // dense pairs are grouped by actor already
+        let actor_movies = dense_pairs.iter().map(|(_, movie)| *movie).collect();
+        // counting sort by movie for the other direction
+        let mut movie_actors = vec![0; dense_pairs.len()];
+        let mut next_slot = movie_offsets.clone();
+        for (actor, movie) in dense_pairs {
+            let slot = &mut next_slot[movie as usize];
+            movie_actors[*slot as usize] = actor;
+            *slot += 1;
+        }
+        let components = Components::from_casts(
+            actor_ids.len(),
+            movie_offsets
+                .windows(2)
+                .map(|row| &movie_actors[row[0] as usize..row[1] as usize]),
+        );
+
+        Connections {
+            actor_ids,
+            movie_ids,
+            actor_offsets,
+            actor_movies,
+            movie_offsets,
+            movie_actors,
+            components,
+        }
+    }
+
+    /// Number of actors with at least one credit.
+    pub fn num_actors(&self) -> usize {
+        self.actor_ids.len()
+    }
+
+    /// Number of movies with at least one credited actor.
+    pub fn num_movies(&self) -> usize {
+        self.movie_ids.len()
+    }
+
+    /// Number of distinct (actor, movie) credits.
+    pub fn num_credits(&self) -> usize {
+        self.actor_movies.len()
+    }
+
+    pub fn actor_index(&self, actor_id: usize) -> Option<ActorIndex> {
+        self.actor_ids
+            .binary_search(&actor_id)
+            .ok()
+            .map(|index| index as ActorIndex)
+    }
+
+    pub fn movie_index(&self, movie_id: usize) -> Option<MovieIndex> {
+        self.movie_ids
+            .binary_search(&movie_id)
+            .ok()
+            .map(|index| index as MovieIndex)
+    }
+
+    pub fn actor_id(&self, actor_index: ActorIndex) -> usize {
+        self.actor_ids[actor_index as usize]
+    }
+
+    pub fn movie_id(&self, movie_index: MovieIndex) -> usize {
+        self.movie_ids[movie_index as usize]
+    }
+
+    /// Movies the actor was credited in.
+    pub fn movies_of(&self, actor_index: ActorIndex) -> &[MovieIndex] {
+        let start = self.actor_offsets[actor_index as usize] as usize;
+        let end = self.actor_offsets[actor_index as usize + 1] as usize;
+        &self.actor_movies[start..end]
+    }
+
+    /// Actors credited in the movie.
+    pub fn cast_of(&self, movie_index: MovieIndex) -> &[ActorIndex] {
+        let start = self.movie_offsets[movie_index as usize] as usize;
+        let end = self.movie_offsets[movie_index as usize + 1] as usize;
+        &self.movie_actors[start..end]
+    }
+
+    /// Connected components of the co-star graph, computed when the index
+    /// is built.
+    pub fn components(&self) -> &Components {
+        &self.components
+    }
+
+    /// Heap bytes used by the index, components included.
+    pub fn heap_size(&self) -> usize {
+        self.csr_heap_size() + self.components.heap_size()
+    }
+
+    /// Heap bytes of the credits alone: the id maps and the CSR arrays that
+    /// replace the hash maps.
+    pub fn csr_heap_size(&self) -> usize {
+        self.actor_ids.capacity() * size_of::<usize>()
+            + self.movie_ids.capacity() * size_of::<usize>()
+            + (self.actor_offsets.capacity() + self.movie_offsets.capacity()) * size_of::<u32>()
+            + self.actor_movies.capacity() * size_of::<MovieIndex>()
+            + self.movie_actors.capacity() * size_of::<ActorIndex>()
+    }
+
+    /// Rough heap bytes the same credits take as a
+    /// `HashMap<usize, HashSet<usize>>` in each direction, which is how they
+    /// used to be stored.
+    pub fn hash_map_size_estimate(&self) -> usize {
+        hash_map_size(&self.actor_offsets) + hash_map_size(&self.movie_offsets)
+    }
+
+    /// One line summary of the index size, for logging at load time.
+    pub fn memory_summary(&self) -> String {
+        const MIB: f64 = 1024.0 * 1024.0;
+        let compact = self.csr_heap_size();
+        let hash_maps = self.hash_map_size_estimate();
+        format!(
+            "{} credits between {} actors and {} movies use {:.1} MiB, saving {:.1} MiB over hash maps (~{:.1} MiB), plus {:.1} MiB of connected components",
+            self.num_credits(),
+            self.num_actors(),
+            self.num_movies(),
+            compact as f64 / MIB,
+            hash_maps.saturating_sub(compact) as f64 / MIB,
+            hash_maps as f64 / MIB,
+            self.components.heap_size() as f64 / MIB,
+        )
+    }
+}
+
+/// Rough heap bytes of a `HashMap<usize, HashSet<usize>>` with one key per
+/// row of a CSR offset array.
+fn hash_map_size(offsets: &[u32]) -> usize {
+    let num_keys = offsets.len() - 1;
+    hash_table_size(num_keys, size_of::<(usize, HashSet<usize>)>())
+        + offsets
+            .windows(2)
+            .map(|row| hash_table_size((row[1] - row[0]) as usize, size_of::<usize>()))
+            .sum::<usize>()
+}
+
+/// Heap bytes of a `hashbrown` table holding `len` entries of `entry_size`
+/// bytes: buckets are a power of two at most 7/8 full, plus one control byte
+/// per bucket and a group of trailing control bytes.
+fn hash_table_size(len: usize, entry_size: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let buckets = if len < 8 {
+        if len < 4 { 4 } else { 8 }
+    } else {
+        (len * 8 / 7).next_power_of_two()
+    };
+    buckets * entry_size + buckets + 16
+}
+
 pub struct MovieDBBuilder;
 
 impl MovieDBBuilder {
@@ -72,6 +353,10 @@ impl MovieDBBuilder {
         Ok(actor_movie_map)
     }
 
+    pub fn get_connections(connections: Vec<(usize, usize)>) -> Connections {
+        Connections::from_pairs(connections)
+    }
+
     pub fn get_actor_movie_maps(connections: Vec<(usize, usize)>) -> (Mapping, Mapping) {
         let mut actor_to_movie = HashMap::new();
         let mut movie_to_actor = HashMap::new();
@@ -90,7 +375,7 @@ impl MovieDBBuilder {
 
     pub fn build_movies_connections(
         dir_path: &Path,
-    ) -> Result<(HashMap<usize, Movie>, Mapping, Mapping), IoError> {
+    ) -> Result<(HashMap<usize, Movie>, Connections), IoError> {
         let movie_file = dir_path.join("movies.csv");
         let movies_reader_handle = thread::spawn(move || MovieDBBuilder::read_movies(&movie_file));
 
@@ -103,9 +388,7 @@ impl MovieDBBuilder {
                         actor_movie_file, err
                     )
                 });
-            let (actor_to_movie, movie_to_actor) =
-                MovieDBBuilder::get_actor_movie_maps(connections);
-            (actor_to_movie, movie_to_actor)
+            MovieDBBuilder::get_connections(connections)
         });
 
         let movies = match movies_reader_handle.join() {
@@ -117,8 +400,8 @@ impl MovieDBBuilder {
             }
         };
 
-        let (actor_to_movie, movie_to_actor) = match am_reader_handle.join() {
-            Ok(maps) => maps,
+        let connections = match am_reader_handle.join() {
+            Ok(connections) => connections,
             Err(_) => {
                 return Err(IoError::other(
                     "Problem reading actor movie pairs from file".to_string(),
@@ -126,7 +409,7 @@ impl MovieDBBuilder {
             }
         };
 
-        Ok((movies, actor_to_movie, movie_to_actor))
+        Ok((movies, connections))
     }
 }
 
@@ -174,7 +457,6 @@ impl Display for Actor {
 }
 
 #[cfg(test)]
-
 mod test {
     use super::*;
 
@@ -224,17 +506,86 @@ mod test {
         let movies = MovieDBBuilder::read_movies(&movie_file).unwrap();
         let actor_movie_file = data_dir.join("connections.csv");
         let pairs = MovieDBBuilder::read_actor_movie_pairs(&actor_movie_file).unwrap();
-        let (actor_to_movies, movie_to_actors) = MovieDBBuilder::get_actor_movie_maps(pairs);
+        let connections = MovieDBBuilder::get_connections(pairs);
         let db = MovieDB {
-            actor_to_movies,
-            movie_to_actors,
+            connections,
             actors,
             movies,
         };
 
         assert_eq!(db.actors.len(), 15);
         assert_eq!(db.movies.len(), 5);
-        assert_eq!(db.actor_to_movies[&102].len(), 2);
-        assert_eq!(db.movie_to_actors[&104257].len(), 4);
+        assert_eq!(db.movies_of_actor(102).count(), 2);
+        assert_eq!(db.actors_of_movie(104257).count(), 4);
+    }
+
+    #[test]
+    fn connections_csr() {
+        // duplicate credits are only stored once
+        let pairs = vec![(30, 7), (10, 7), (10, 5), (20, 5), (10, 7)];
+        let connections = Connections::from_pairs(pairs);
+
+        assert_eq!(connections.num_actors(), 3);
+        assert_eq!(connections.num_movies(), 2);
+        assert_eq!(connections.num_credits(), 4);
+
+        let actor = connections.actor_index(10).unwrap();
+        assert_eq!(connections.actor_id(actor), 10);
+        let movies: Vec<usize> = connections
+            .movies_of(actor)
+            .iter()
+            .map(|&movie| connections.movie_id(movie))
+            .collect();
+        assert_eq!(movies, vec![5, 7]);
+
+        let movie = connections.movie_index(7).unwrap();
+        let mut cast: Vec<usize> = connections
+            .cast_of(movie)
+            .iter()
+            .map(|&actor| connections.actor_id(actor))
+            .collect();
+        cast.sort();
+        assert_eq!(cast, vec![10, 30]);
+
+        assert_eq!(connections.actor_index(40), None);
+        assert_eq!(connections.movie_index(6), None);
+    }
+
+    #[test]
+    fn movie_db_connected() {
+        let db = MovieDB {
+            connections: Connections::from_pairs(vec![(1, 10), (2, 10), (3, 11)]),
+            actors: HashMap::new(),
+            movies: HashMap::new(),
+        };
+        assert!(db.connected(1, 2));
+        assert!(!db.connected(1, 3));
+        // an actor without credits
+        assert!(db.connected(4, 4));
+        assert!(!db.connected(1, 4));
+    }
+
+    #[test]
+    fn movie_db_maps_match_pairs() {
+        let data_file = PathBuf::from("data/new_small/connections.csv");
+        let pairs = MovieDBBuilder::read_actor_movie_pairs(&data_file).unwrap();
+        let (actor_to_movies, movie_to_actors) =
+            MovieDBBuilder::get_actor_movie_maps(pairs.clone());
+        let db = MovieDB {
+            connections: Connections::from_pairs(pairs),
+            actors: HashMap::new(),
+            movies: HashMap::new(),
+        };
+        assert_eq!(db.actor_to_movies(), actor_to_movies);
+        assert_eq!(db.movie_to_actors(), movie_to_actors);
+    }
+
+    #[test]
+    fn connections_smaller_than_hash_maps() {
+        let data_file = PathBuf::from("data/new_small/connections.csv");
+        let pairs = MovieDBBuilder::read_actor_movie_pairs(&data_file).unwrap();
+        let connections = Connections::from_pairs(pairs);
+        assert!(connections.csr_heap_size() < connections.hash_map_size_estimate());
+        assert!(connections.csr_heap_size() < connections.heap_size());
     }
 }
@@ -0,0 +1,153 @@
+use super::ActorIndex;
+
+/// Dense id of a connected component of the co-star graph. Components are
+/// numbered by decreasing size, so component 0 is the largest.
+pub type ComponentId = u32;
+
+/// Connected components of the co-star graph: two actors are in the same
+/// component when some chain of co-stars links them.
+pub struct Components {
+    component_of: Vec<ComponentId>,
+    sizes: Vec<usize>,
+}
+
+impl Components {
+    /// Joins the cast of every movie, for `num_actors` actors indexed
+    /// densely from 0.
+    pub(crate) fn from_casts<'a, I>(num_actors: usize, casts: I) -> Components
+    where
+        I: IntoIterator<Item = &'a [ActorIndex]>,
+    {
+        let mut sets = DisjointSets::new(num_actors);
+        for cast in casts {
+            if let Some((&first, rest)) = cast.split_first() {
+                for &actor in rest {
+                    sets.union(first, actor);
+                }
+            }
+        }
+
+        // number the roots by decreasing size, ties by lowest actor index
+        let mut roots: Vec<ActorIndex> = (0..num_actors as ActorIndex)
+            .filter(|&actor| sets.find(actor) == actor)
+            .collect();
+        roots.sort_by_key(|&root| std::cmp::Reverse(sets.size[root as usize]));
+        let mut id_of_root = vec![0; num_actors];
+        let mut sizes = Vec::with_capacity(roots.len());
+        for (component, &root) in roots.iter().enumerate() {
+            id_of_root[root as usize] = component as ComponentId;
+            sizes.push(sets.size[root as usize] as usize);
+        }
+        let component_of = (0..num_actors as ActorIndex)
+            .map(|actor| id_of_root[sets.find(actor) as usize])
+            .collect();
+
+        Components {
+            component_of,
+            sizes,
+        }
+    }
+
+    pub fn component_of(&self, actor_index: ActorIndex) -> ComponentId {
+        self.component_of[actor_index as usize]
+    }
+
+    /// Number of actors in the component.
+    pub fn size(&self, component: ComponentId) -> usize {
+        self.sizes[component as usize]
+    }
+
+    /// Size of every component, largest first.
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    pub fn num_components(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// Heap bytes used by the component ids.
+    pub fn heap_size(&self) -> usize {
+        self.component_of.capacity() * size_of::<ComponentId>()
+            + self.sizes.capacity() * size_of::<usize>()
+    }
+}
+
+/// Union-find over actor indices, with union by size and path halving.
+struct DisjointSets {
+    parent: Vec<ActorIndex>,
+    size: Vec<u32>,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> DisjointSets {
+        DisjointSets {
+            parent: (0..len as ActorIndex).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    fn find(&mut self, mut actor: ActorIndex) -> ActorIndex {
+        while self.parent[actor as usize] != actor {
+            let grandparent = self.parent[self.parent[actor as usize] as usize];
+            self.parent[actor as usize] = grandparent;
+            actor = grandparent;
+        }
+        actor
+    }
+
+    fn union(&mut self, a: ActorIndex, b: ActorIndex) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a as usize] < self.size[b as usize] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b as usize] = a;
+        self.size[a as usize] += self.size[b as usize];
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::{Connections, MovieDBBuilder};
+
+    #[test]
+    fn components_from_casts() {
+        let casts: [&[ActorIndex]; 4] = [&[0, 1], &[1, 2], &[3, 4], &[]];
+        let components = Components::from_casts(6, casts);
+        assert_eq!(components.sizes(), &[3, 2, 1]);
+        assert_eq!(components.num_components(), 3);
+        assert_eq!(components.component_of(0), 0);
+        assert_eq!(components.component_of(2), 0);
+        assert_eq!(components.component_of(4), 1);
+        assert_eq!(components.component_of(5), 2);
+        assert_eq!(components.size(1), 2);
+    }
+
+    #[test]
+    fn components_of_connections() {
+        // two separate pairs of co-stars, one of them in two movies
+        let connections =
+            Connections::from_pairs(vec![(1, 10), (2, 10), (3, 11), (4, 11), (3, 12)]);
+        let components = connections.components();
+        assert_eq!(components.sizes(), &[2, 2]);
+        let component_of =
+            |actor_id| components.component_of(connections.actor_index(actor_id).unwrap());
+        assert_eq!(component_of(1), component_of(2));
+        assert_eq!(component_of(3), component_of(4));
+        assert_ne!(component_of(1), component_of(3));
+    }
+
+    #[test]
+    fn components_small_dataset() {
+        let pairs =
+            MovieDBBuilder::read_actor_movie_pairs(&"data/new_small/connections.csv".into())
+                .unwrap();
+        let connections = MovieDBBuilder::get_connections(pairs);
+        // every movie shares an actor with another one
+        assert_eq!(connections.components().sizes(), &[15]);
+    }
+}
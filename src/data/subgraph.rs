@@ -0,0 +1,249 @@
+use super::{ActorIndex, MovieDB, MovieIndex};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Actors within some hops of a few starting actors, with the movies that
+/// link them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subgraph {
+    /// Actor ids with their hops from the nearest starting actor, by hops
+    /// then id.
+    pub actors: Vec<(usize, u32)>,
+    /// Ids of the movies at least two of the actors were in, by id.
+    pub movies: Vec<usize>,
+    /// (actor id, movie id) credits between the actors and movies, by actor
+    /// then movie.
+    pub credits: Vec<(usize, usize)>,
+}
+
+impl Subgraph {
+    pub fn num_nodes(&self) -> usize {
+        self.actors.len() + self.movies.len()
+    }
+}
+
+impl MovieDB {
+    /// Extracts the subgraph induced by the actors at most `max_hops` hops
+    /// from any of `actor_ids`, and the movies linking two or more of them.
+    ///
+    /// When that has more than `max_nodes` actors and movies, the actors
+    /// with the most credits are dropped first, then if need be the movies
+    /// with the biggest cast among the rest. The starting actors are always
+    /// kept, and so is everyone still within `max_hops` of them without the
+    /// dropped hubs.
+    pub fn subgraph(
+        &self,
+        actor_ids: &[usize],
+        max_hops: u32,
+        max_nodes: usize,
+    ) -> Result<Subgraph, &'static str> {
+        let mut actor_ids = actor_ids.to_vec();
+        actor_ids.sort_unstable();
+        actor_ids.dedup();
+        if actor_ids.len() > max_nodes {
+            return Err("More starting actors than the subgraph may have nodes");
+        }
+        let connections = &self.connections;
+        let sources: Vec<ActorIndex> = actor_ids
+            .iter()
+            .filter_map(|&actor_id| connections.actor_index(actor_id))
+            .collect();
+
+        // everyone in reach, then drop hubs until it fits
+        let reached = self.reach(&sources, max_hops, |_| true, |_| true);
+        let mut cast_sizes: HashMap<MovieIndex, usize> = HashMap::new();
+        for &actor in reached.keys() {
+            for &movie in connections.movies_of(actor) {
+                *cast_sizes.entry(movie).or_default() += 1;
+            }
+        }
+        // starting actors without credits are nodes too
+        let mut num_nodes = actor_ids.len() - sources.len() + reached.len();
+        num_nodes += cast_sizes.values().filter(|&&size| size >= 2).count();
+
+        let mut hubs: Vec<ActorIndex> = reached
+            .iter()
+            .filter(|&(_, &hops)| hops > 0)
+            .map(|(&actor, _)| actor)
+            .collect();
+        hubs.sort_unstable_by_key(|&actor| (Reverse(connections.movies_of(actor).len()), actor));
+        let mut dropped_actors = HashSet::new();
+        for actor in hubs {
+            if num_nodes <= max_nodes {
+                break;
+            }
+            dropped_actors.insert(actor);
+            num_nodes -= 1;
+            for movie in connections.movies_of(actor) {
+                let size = cast_sizes.get_mut(movie).unwrap();
+                *size -= 1;
+                if *size == 1 {
+                    num_nodes -= 1;
+                }
+            }
+        }
+        let mut big_casts: Vec<(MovieIndex, usize)> = cast_sizes
+            .into_iter()
+            .filter(|&(_, size)| size >= 2)
+            .collect();
+        big_casts.sort_unstable_by_key(|&(movie, size)| (Reverse(size), movie));
+        let dropped_movies: HashSet<MovieIndex> = big_casts
+            .into_iter()
+            .take(num_nodes.saturating_sub(max_nodes))
+            .map(|(movie, _)| movie)
+            .collect();
+
+        let kept = self.reach(
+            &sources,
+            max_hops,
+            |actor| !dropped_actors.contains(&actor),
+            |movie| !dropped_movies.contains(&movie),
+        );
+        Ok(self.induced_subgraph(&actor_ids, &kept, &dropped_movies))
+    }
+
+    /// Hops to every allowed actor within `max_hops` of the sources through
+    /// the allowed movies.
+    fn reach(
+        &self,
+        sources: &[ActorIndex],
+        max_hops: u32,
+        allows_actor: impl Fn(ActorIndex) -> bool,
+        allows_movie: impl Fn(MovieIndex) -> bool,
+    ) -> HashMap<ActorIndex, u32> {
+        let connections = &self.connections;
+        let mut hops: HashMap<ActorIndex, u32> = sources.iter().map(|&actor| (actor, 0)).collect();
+        let mut frontier = sources.to_vec();
+        for distance in 1..=max_hops {
+            let mut next = Vec::new();
+            for actor in frontier {
+                for &movie in connections.movies_of(actor) {
+                    if !allows_movie(movie) {
+                        continue;
+                    }
+                    for &co_star in connections.cast_of(movie) {
+                        if allows_actor(co_star) && !hops.contains_key(&co_star) {
+                            hops.insert(co_star, distance);
+                            next.push(co_star);
+                        }
+                    }
+                }
+            }
+            frontier = next;
+        }
+        hops
+    }
+
+    fn induced_subgraph(
+        &self,
+        actor_ids: &[usize],
+        hops: &HashMap<ActorIndex, u32>,
+        dropped_movies: &HashSet<MovieIndex>,
+    ) -> Subgraph {
+        let connections = &self.connections;
+        let mut cast_sizes: HashMap<MovieIndex, usize> = HashMap::new();
+        for &actor in hops.keys() {
+            for &movie in connections.movies_of(actor) {
+                if !dropped_movies.contains(&movie) {
+                    *cast_sizes.entry(movie).or_default() += 1;
+                }
+            }
+        }
+        let mut credits = Vec::new();
+        for &actor in hops.keys() {
+            for &movie in connections.movies_of(actor) {
+                if cast_sizes.get(&movie).is_some_and(|&size| size >= 2) {
+                    credits.push((connections.actor_id(actor), connections.movie_id(movie)));
+                }
+            }
+        }
+        credits.sort_unstable();
+        let mut movies: Vec<usize> = cast_sizes
+            .into_iter()
+            .filter(|&(_, size)| size >= 2)
+            .map(|(movie, _)| connections.movie_id(movie))
+            .collect();
+        movies.sort_unstable();
+        let mut actors: Vec<(usize, u32)> = hops
+            .iter()
+            .map(|(&actor, &hops)| (connections.actor_id(actor), hops))
+            .collect();
+        actors.extend(
+            actor_ids
+                .iter()
+                .filter(|&&actor_id| connections.actor_index(actor_id).is_none())
+                .map(|&actor_id| (actor_id, 0)),
+        );
+        actors.sort_unstable_by_key(|&(actor_id, hops)| (hops, actor_id));
+        Subgraph {
+            actors,
+            movies,
+            credits,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::test::make_test_db;
+
+    fn actor_ids(subgraph: &super::Subgraph) -> Vec<usize> {
+        subgraph
+            .actors
+            .iter()
+            .map(|&(actor_id, _)| actor_id)
+            .collect()
+    }
+
+    #[test]
+    fn neighbourhood() {
+        let db = make_test_db();
+        let subgraph = db.subgraph(&[102], 1, 100).unwrap();
+        // the casts of A Few Good Men and Apollo 13, two of whom were also in
+        // Forrest Gump
+        assert_eq!(subgraph.movies, vec![104257, 109830, 112384]);
+        assert_eq!(
+            actor_ids(&subgraph),
+            vec![102, 129, 158, 193, 197, 200, 641]
+        );
+        assert_eq!(subgraph.actors[0], (102, 0));
+        assert!(subgraph.actors[1..].iter().all(|&(_, hops)| hops == 1));
+        assert_eq!(subgraph.credits.len(), 10);
+        assert!(subgraph.credits.contains(&(158, 109830)));
+        assert_eq!(subgraph.num_nodes(), 10);
+
+        // Robin Wright is 2 hops away, but the rest of The Princess Bride 3
+        let subgraph = db.subgraph(&[102], 2, 100).unwrap();
+        assert_eq!(subgraph.movies, vec![95953, 104257, 109830, 112384]);
+        assert!(subgraph.actors.contains(&(705, 2)));
+        assert!(!subgraph.credits.contains(&(705, 93779)));
+
+        let subgraph = db.subgraph(&[102, 144], 0, 100).unwrap();
+        assert_eq!(subgraph.actors, vec![(102, 0), (144, 0)]);
+        assert!(subgraph.movies.is_empty());
+    }
+
+    #[test]
+    fn hubs_dropped_first() {
+        let db = make_test_db();
+        // Tom Cruise, Tom Hanks and Gary Sinise have two credits each, the
+        // most, and Tom Cruise goes first by id
+        let subgraph = db.subgraph(&[102], 1, 9).unwrap();
+        assert_eq!(subgraph.num_nodes(), 9);
+        assert_eq!(actor_ids(&subgraph), vec![102, 158, 193, 197, 200, 641]);
+        assert_eq!(subgraph.movies, vec![104257, 109830, 112384]);
+
+        // without Tom Hanks, Gary Sinise is all that is left of Forrest Gump
+        let subgraph = db.subgraph(&[102], 1, 7).unwrap();
+        assert_eq!(subgraph.num_nodes(), 7);
+        assert_eq!(actor_ids(&subgraph), vec![102, 193, 197, 200, 641]);
+        assert_eq!(subgraph.movies, vec![104257, 112384]);
+
+        // the starting actors stay, the movies go
+        let subgraph = db.subgraph(&[158, 641], 1, 2).unwrap();
+        assert_eq!(subgraph.actors, vec![(158, 0), (641, 0)]);
+        assert!(subgraph.movies.is_empty() && subgraph.credits.is_empty());
+
+        assert!(db.subgraph(&[158, 641], 1, 1).is_err());
+    }
+}
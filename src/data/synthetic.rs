@@ -0,0 +1,37 @@
+//! Random co-star graphs for the tests and benchmarks.
+
+/// Credits of a random co-star graph: `num_actors` actors, numbered from
+/// `first_id`, spread over `num_actors / 2` movies of two to
+/// `max_cast_size` actors each, and every actor in at least one movie.
+/// Small casts keep the graph sparse so searches have to visit a large part
+/// of it, like the weakly connected pairs in `data/new_large`.
+///
+/// The movies are numbered from `first_id` too. The graph is the same from
+/// run to run.
+pub fn random_credits(
+    first_id: usize,
+    num_actors: usize,
+    max_cast_size: usize,
+) -> Vec<(usize, usize)> {
+    // xorshift from a fixed seed
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize
+    };
+
+    let mut pairs = Vec::new();
+    let num_movies = num_actors / 2;
+    for movie_id in first_id..first_id + num_movies {
+        let cast_size = 2 + next() % (max_cast_size - 1);
+        for _ in 0..cast_size {
+            pairs.push((first_id + next() % num_actors, movie_id));
+        }
+    }
+    for actor_id in first_id..first_id + num_actors {
+        pairs.push((actor_id, first_id + next() % num_movies));
+    }
+    pairs
+}
@@ -0,0 +1,332 @@
+//! Writers of the co-star graph, or the part of it around a path or a few
+//! actors, in formats that graph tools read: DOT for Graphviz, GraphML and
+//! GEXF for Gephi.
+//!
+//! Actors and movies are both nodes, and every credit is an edge between an
+//! actor and a movie. Actor node ids are `a` and the actor id, movie node ids
+//! `m` and the movie id.
+use crate::data::{MovieDB, Subgraph};
+use crate::graph::Node;
+use std::io::{Result as IoResult, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    GraphMl,
+    Gexf,
+}
+
+/// The actors, movies and credits to export.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportGraph {
+    pub actor_ids: Vec<usize>,
+    pub movie_ids: Vec<usize>,
+    /// (actor id, movie id) pairs.
+    pub credits: Vec<(usize, usize)>,
+}
+
+impl ExportGraph {
+    /// The actors of a path, the movies between them and the credits that
+    /// link each movie to the actors on either side.
+    pub fn from_path(path: &[Node]) -> ExportGraph {
+        let mut graph = ExportGraph {
+            actor_ids: path.iter().map(|node| node.actor_id).collect(),
+            ..Default::default()
+        };
+        for pair in path.windows(2) {
+            if let Some(movie_id) = pair[1].movie_id {
+                graph.movie_ids.push(movie_id);
+                graph.credits.push((pair[0].actor_id, movie_id));
+                graph.credits.push((pair[1].actor_id, movie_id));
+            }
+        }
+        graph.actor_ids.dedup();
+        graph.movie_ids.sort_unstable();
+        graph.movie_ids.dedup();
+        graph.credits.sort_unstable();
+        graph.credits.dedup();
+        graph
+    }
+
+    pub fn from_subgraph(subgraph: &Subgraph) -> ExportGraph {
+        ExportGraph {
+            actor_ids: subgraph
+                .actors
+                .iter()
+                .map(|&(actor_id, _)| actor_id)
+                .collect(),
+            movie_ids: subgraph.movies.clone(),
+            credits: subgraph.credits.clone(),
+        }
+    }
+
+    /// Every actor and movie, credited or not, and every credit.
+    pub fn whole(movie_db: &MovieDB) -> ExportGraph {
+        let mut actor_ids: Vec<usize> = movie_db.actors.keys().copied().collect();
+        actor_ids.sort_unstable();
+        let mut movie_ids: Vec<usize> = movie_db.movies.keys().copied().collect();
+        movie_ids.sort_unstable();
+        let connections = &movie_db.connections;
+        let mut credits = Vec::with_capacity(connections.num_credits());
+        for actor in 0..connections.num_actors() as u32 {
+            let actor_id = connections.actor_id(actor);
+            for &movie in connections.movies_of(actor) {
+                credits.push((actor_id, connections.movie_id(movie)));
+            }
+        }
+        ExportGraph {
+            actor_ids,
+            movie_ids,
+            credits,
+        }
+    }
+}
+
+/// Writes the graph in the format, with the name and birth year of every
+/// actor and the title and year of every movie as node attributes.
+pub fn write_graph(
+    graph: &ExportGraph,
+    format: GraphFormat,
+    movie_db: &MovieDB,
+    out: &mut impl Write,
+) -> IoResult<()> {
+    match format {
+        GraphFormat::Dot => write_dot(graph, movie_db, out),
+        GraphFormat::GraphMl => write_graphml(graph, movie_db, out),
+        GraphFormat::Gexf => write_gexf(graph, movie_db, out),
+    }
+}
+
+fn write_dot(graph: &ExportGraph, movie_db: &MovieDB, out: &mut impl Write) -> IoResult<()> {
+    writeln!(out, "graph costars {{")?;
+    for &actor_id in &graph.actor_ids {
+        write!(out, "  a{} [type=\"actor\"", actor_id)?;
+        if let Some(actor) = movie_db.actors.get(&actor_id) {
+            let name = escape_dot(&actor.full_name);
+            write!(out, ", label=\"{}\", name=\"{}\"", name, name)?;
+            if let Some(birth_year) = actor.birth_year {
+                write!(out, ", birth_year={}", birth_year)?;
+            }
+        }
+        writeln!(out, "];")?;
+    }
+    for &movie_id in &graph.movie_ids {
+        write!(out, "  m{} [type=\"movie\", shape=box", movie_id)?;
+        if let Some(movie) = movie_db.movies.get(&movie_id) {
+            let title = escape_dot(&movie.title);
+            write!(
+                out,
+                ", label=\"{} ({})\", title=\"{}\", year={}",
+                title, movie.year, title, movie.year
+            )?;
+        }
+        writeln!(out, "];")?;
+    }
+    for &(actor_id, movie_id) in &graph.credits {
+        writeln!(out, "  a{} -- m{};", actor_id, movie_id)?;
+    }
+    writeln!(out, "}}")
+}
+
+fn write_graphml(graph: &ExportGraph, movie_db: &MovieDB, out: &mut impl Write) -> IoResult<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    for (key, kind) in NODE_ATTRIBUTES {
+        let kind = if kind == "integer" { "int" } else { kind };
+        writeln!(
+            out,
+            r#"  <key id="{key}" for="node" attr.name="{key}" attr.type="{kind}"/>"#
+        )?;
+    }
+    writeln!(out, r#"  <graph id="costars" edgedefault="undirected">"#)?;
+    for_each_node(graph, movie_db, |id, attributes| {
+        write!(out, r#"    <node id="{}">"#, id)?;
+        for (key, value) in attributes {
+            write!(out, r#"<data key="{}">{}</data>"#, key, escape_xml(&value))?;
+        }
+        writeln!(out, "</node>")
+    })?;
+    for &(actor_id, movie_id) in &graph.credits {
+        writeln!(
+            out,
+            r#"    <edge source="a{}" target="m{}"/>"#,
+            actor_id, movie_id
+        )?;
+    }
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</graphml>")
+}
+
+fn write_gexf(graph: &ExportGraph, movie_db: &MovieDB, out: &mut impl Write) -> IoResult<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#)?;
+    writeln!(out, r#"  <graph defaultedgetype="undirected">"#)?;
+    writeln!(out, r#"    <attributes class="node">"#)?;
+    for (key, kind) in NODE_ATTRIBUTES {
+        writeln!(
+            out,
+            r#"      <attribute id="{key}" title="{key}" type="{kind}"/>"#
+        )?;
+    }
+    writeln!(out, "    </attributes>")?;
+    writeln!(out, "    <nodes>")?;
+    for_each_node(graph, movie_db, |id, attributes| {
+        let label = attributes
+            .iter()
+            .find(|(key, _)| *key == "name" || *key == "title")
+            .map_or(id.as_str(), |(_, value)| value.as_str());
+        write!(
+            out,
+            r#"      <node id="{}" label="{}"><attvalues>"#,
+            id,
+            escape_xml(label)
+        )?;
+        for (key, value) in &attributes {
+            write!(
+                out,
+                r#"<attvalue for="{}" value="{}"/>"#,
+                key,
+                escape_xml(value)
+            )?;
+        }
+        writeln!(out, "</attvalues></node>")
+    })?;
+    writeln!(out, "    </nodes>")?;
+    writeln!(out, "    <edges>")?;
+    for (edge, &(actor_id, movie_id)) in graph.credits.iter().enumerate() {
+        writeln!(
+            out,
+            r#"      <edge id="{}" source="a{}" target="m{}"/>"#,
+            edge, actor_id, movie_id
+        )?;
+    }
+    writeln!(out, "    </edges>")?;
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</gexf>")
+}
+
+/// Node attributes of the XML formats, with their GEXF type.
+const NODE_ATTRIBUTES: [(&str, &str); 5] = [
+    ("type", "string"),
+    ("name", "string"),
+    ("birth_year", "integer"),
+    ("title", "string"),
+    ("year", "integer"),
+];
+
+/// Calls `write_node` with the id and known attributes of every node, actors
+/// first.
+fn for_each_node(
+    graph: &ExportGraph,
+    movie_db: &MovieDB,
+    mut write_node: impl FnMut(String, Vec<(&'static str, String)>) -> IoResult<()>,
+) -> IoResult<()> {
+    for &actor_id in &graph.actor_ids {
+        let mut attributes = vec![("type", "actor".to_string())];
+        if let Some(actor) = movie_db.actors.get(&actor_id) {
+            attributes.push(("name", actor.full_name.clone()));
+            if let Some(birth_year) = actor.birth_year {
+                attributes.push(("birth_year", birth_year.to_string()));
+            }
+        }
+        write_node(format!("a{}", actor_id), attributes)?;
+    }
+    for &movie_id in &graph.movie_ids {
+        let mut attributes = vec![("type", "movie".to_string())];
+        if let Some(movie) = movie_db.movies.get(&movie_id) {
+            attributes.push(("title", movie.title.clone()));
+            attributes.push(("year", movie.year.to_string()));
+        }
+        write_node(format!("m{}", movie_id), attributes)?;
+    }
+    Ok(())
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::test::make_test_db;
+
+    fn export(graph: &ExportGraph, format: GraphFormat) -> String {
+        let db = make_test_db();
+        let mut out = Vec::new();
+        write_graph(graph, format, &db, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn cruise_to_hanks() -> Vec<Node> {
+        vec![
+            Node::new(129, None, None),
+            Node::new(102, Some(0), Some(104257)),
+            Node::new(158, Some(1), Some(112384)),
+        ]
+    }
+
+    #[test]
+    fn graph_of_path() {
+        let graph = ExportGraph::from_path(&cruise_to_hanks());
+        assert_eq!(graph.actor_ids, vec![129, 102, 158]);
+        assert_eq!(graph.movie_ids, vec![104257, 112384]);
+        assert_eq!(
+            graph.credits,
+            vec![(102, 104257), (102, 112384), (129, 104257), (158, 112384)]
+        );
+        assert_eq!(ExportGraph::from_path(&[]), ExportGraph::default());
+
+        let db = make_test_db();
+        let whole = ExportGraph::whole(&db);
+        assert_eq!(whole.actor_ids.len(), 15);
+        assert_eq!(whole.movie_ids.len(), 5);
+        assert_eq!(whole.credits.len(), 20);
+    }
+
+    #[test]
+    fn dot() {
+        let dot = export(
+            &ExportGraph::from_path(&cruise_to_hanks()),
+            GraphFormat::Dot,
+        );
+        assert!(dot.starts_with("graph costars {\n"));
+        assert!(dot.contains(
+            "  a102 [type=\"actor\", label=\"kevin bacon\", name=\"kevin bacon\", birth_year=1958];\n"
+        ));
+        assert!(dot.contains("  m112384 [type=\"movie\", shape=box, label=\"Apollo 13 (1995)\", title=\"Apollo 13\", year=1995];\n"));
+        assert!(dot.contains("  a129 -- m104257;\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(escape_dot(r#"say "hi" \o/"#), r#"say \"hi\" \\o/"#);
+    }
+
+    #[test]
+    fn graphml_and_gexf() {
+        let graph = ExportGraph::from_path(&cruise_to_hanks());
+        let graphml = export(&graph, GraphFormat::GraphMl);
+        assert!(graphml.contains(
+            r#"<key id="birth_year" for="node" attr.name="birth_year" attr.type="int"/>"#
+        ));
+        assert!(graphml.contains(r#"<node id="m104257"><data key="type">movie</data><data key="title">A Few Good Men</data><data key="year">1992</data></node>"#));
+        assert!(graphml.contains(r#"<edge source="a158" target="m112384"/>"#));
+        assert_eq!(graphml.matches("<node ").count(), 5);
+
+        let gexf = export(&graph, GraphFormat::Gexf);
+        assert!(gexf.contains(r#"<node id="a158" label="tom hanks"><attvalues><attvalue for="type" value="actor"/><attvalue for="name" value="tom hanks"/><attvalue for="birth_year" value="1956"/></attvalues></node>"#));
+        assert!(gexf.contains(r#"<edge id="3" source="a158" target="m112384"/>"#));
+        assert_eq!(gexf.matches("<edge ").count(), 4);
+
+        assert_eq!(escape_xml("Tom & <Jerry>"), "Tom &amp; &lt;Jerry&gt;");
+    }
+}
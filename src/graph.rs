@@ -1,6 +1,33 @@
-use crate::data::MovieDB;
-// TODO: make nodes hashable and store them in hashsets or b-trees instead of hashmaps or vectors
-//derive debug
+use crate::data::{ActorIndex, MovieDB, MovieIndex};
+use serde::{Serialize, Serializer};
+use std::collections::{HashSet, VecDeque};
+use std::fmt::{self, Display};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{Span, field};
+
+mod all_paths;
+mod chronological;
+mod distances;
+mod endpoints;
+mod index_file;
+mod landmarks;
+mod oracle;
+mod parallel;
+mod via;
+mod weighted;
+mod yen;
+
+pub use all_paths::all_shortest_paths;
+pub use chronological::{YearOrder, chronological_path};
+pub use distances::{DistanceProfile, distance_profile};
+pub use endpoints::{Endpoint, EndpointPath, endpoint_path};
+pub use landmarks::{LANDMARKS_FILE, Landmarks, landmark_path};
+pub use oracle::{DistanceOracle, ORACLE_FILE};
+pub use parallel::{ParallelOptions, parallel_shortest_path};
+pub use via::{Via, via_path};
+pub use weighted::{HopCost, PreferProlificActors, PreferRecent, PreferSmallCasts, cheapest_path};
+pub use yen::k_shortest_paths;
 
 type NodeIndex = usize;
 type ActorId = usize;
@@ -19,9 +46,212 @@ impl PartialEq for Node {
     }
 }
 
+/// Restrictions on the actors and movies a search may pass through.
+#[derive(Debug, Default, Clone)]
+pub struct SearchOptions {
+    /// Actors the path may not go through. Excluding one of the two ends
+    /// leaves no path.
+    pub excluded_actors: HashSet<ActorId>,
+    /// Movies the path may not use.
+    pub excluded_movies: HashSet<MovieId>,
+    /// Skip movies with a bigger cast, such as documentaries that would link
+    /// almost everyone.
+    pub max_cast_size: Option<usize>,
+    /// Hide movies released before this year.
+    pub min_year: Option<u32>,
+    /// Hide movies released after this year, to search the graph as it was
+    /// at the end of that year.
+    pub max_year: Option<u32>,
+}
+
+impl SearchOptions {
+    /// Whether every actor and movie is allowed, so precomputed indices
+    /// such as [`DistanceOracle`] can answer the search.
+    pub fn is_unrestricted(&self) -> bool {
+        self.excluded_actors.is_empty()
+            && self.excluded_movies.is_empty()
+            && self.max_cast_size.is_none()
+            && self.min_year.is_none()
+            && self.max_year.is_none()
+    }
+
+    pub fn allows_actor(&self, actor_id: ActorId) -> bool {
+        !self.excluded_actors.contains(&actor_id)
+    }
+
+    /// Whether the search may use the movie. Movies with an unknown release
+    /// year are hidden as soon as a year window is set.
+    pub fn allows_movie(&self, movie_index: MovieIndex, movie_db: &MovieDB) -> bool {
+        let connections = &movie_db.connections;
+        if let Some(max_cast_size) = self.max_cast_size
+            && connections.cast_of(movie_index).len() > max_cast_size
+        {
+            return false;
+        }
+        let movie_id = connections.movie_id(movie_index);
+        if self.min_year.is_some() || self.max_year.is_some() {
+            let Some(movie) = movie_db.movies.get(&movie_id) else {
+                return false;
+            };
+            if self.min_year.is_some_and(|min_year| movie.year < min_year)
+                || self.max_year.is_some_and(|max_year| movie.year > max_year)
+            {
+                return false;
+            }
+        }
+        self.excluded_movies.is_empty() || !self.excluded_movies.contains(&movie_id)
+    }
+}
+
+/// How much work a search may do before giving up. The default has no
+/// limits and is never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    /// Most hops the path may have.
+    pub max_depth: Option<usize>,
+    /// Most actors the search may expand.
+    pub max_nodes_explored: Option<usize>,
+    /// When to give up, checked every thousand actors.
+    pub deadline: Option<Instant>,
+    /// Stops the search at the next actor once cancelled, for example when
+    /// the client that asked for it goes away.
+    pub cancellation: CancellationToken,
+}
+
+impl SearchLimits {
+    /// Fails once the search is cancelled or has expanded too many actors.
+    fn check(&self, num_explored: usize) -> Result<(), SearchError> {
+        if self.cancellation.is_cancelled() {
+            return Err(SearchError::Cancelled);
+        }
+        if self
+            .max_nodes_explored
+            .is_some_and(|max_nodes| num_explored >= max_nodes)
+        {
+            return Err(SearchError::BudgetExceeded(Budget::NodesExplored));
+        }
+        Ok(())
+    }
+
+    fn check_deadline(&self) -> Result<(), SearchError> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                Err(SearchError::BudgetExceeded(Budget::Deadline))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How much work a search did.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SearchStats {
+    /// Actors reached from either end.
+    pub nodes_created: usize,
+    /// Actors whose co-stars were looked up.
+    pub nodes_explored: usize,
+    /// Size of the frontier of the search from the source at each level it
+    /// expanded.
+    pub forward_frontier_sizes: Vec<usize>,
+    /// The same for the search from the target.
+    pub backward_frontier_sizes: Vec<usize>,
+    #[serde(rename = "elapsed_ms", serialize_with = "serialize_millis")]
+    pub elapsed: Duration,
+}
+
+impl SearchStats {
+    /// Records the stats on the fields of `span` with the same names.
+    pub fn record(&self, span: &Span) {
+        span.record("nodes_created", self.nodes_created);
+        span.record("nodes_explored", self.nodes_explored);
+        span.record(
+            "forward_frontier_sizes",
+            field::debug(&self.forward_frontier_sizes),
+        );
+        span.record(
+            "backward_frontier_sizes",
+            field::debug(&self.backward_frontier_sizes),
+        );
+        span.record("elapsed_ms", self.elapsed.as_secs_f64() * 1000.0);
+    }
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "nodes created: {}", self.nodes_created)?;
+        writeln!(f, "nodes explored: {}", self.nodes_explored)?;
+        writeln!(
+            f,
+            "frontier sizes from the source: {:?}",
+            self.forward_frontier_sizes
+        )?;
+        writeln!(
+            f,
+            "frontier sizes from the target: {:?}",
+            self.backward_frontier_sizes
+        )?;
+        write!(f, "elapsed: {:.1?}", self.elapsed)
+    }
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+/// The limit of [`SearchLimits`] a search ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    Depth,
+    NodesExplored,
+    Deadline,
+}
+
+/// Why [`shortest_path`] or another search returned no path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchError {
+    /// No chain of co-stars links the actors through what the options allow.
+    NoPath,
+    /// The options exclude an actor or movie the search has to start from
+    /// or go through.
+    Excluded,
+    /// Every chain through the actor or movie the search has to go through
+    /// has an actor in it twice.
+    RepeatsActor,
+    /// The search gave up before finding out whether there is a path.
+    BudgetExceeded(Budget),
+    Cancelled,
+}
+
+impl SearchError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            SearchError::NoPath => "no path found",
+            SearchError::Excluded => "an actor or movie the search has to use is excluded",
+            SearchError::RepeatsActor => "no chain goes through it without repeating an actor",
+            SearchError::BudgetExceeded(Budget::Depth) => "no path within the maximum depth",
+            SearchError::BudgetExceeded(Budget::NodesExplored) => {
+                "explored the maximum number of actors without finding a path"
+            }
+            SearchError::BudgetExceeded(Budget::Deadline) => {
+                "ran past the deadline without finding a path"
+            }
+            SearchError::Cancelled => "search was cancelled",
+        }
+    }
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// Marks an actor that no node of a [`Graph`] has reached yet.
+const UNVISITED: u32 = u32::MAX;
+
 pub struct Graph {
-    frontier: Vec<NodeIndex>,
-    explored: Vec<ActorId>, // actor ids
+    frontier: VecDeque<NodeIndex>,
+    visited: Vec<u32>, // actor index -> index of the node that reached it, or UNVISITED
     nodes: Vec<Node>,
 }
 
@@ -35,58 +265,101 @@ impl Node {
     }
 }
 
-impl Default for Graph {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl Graph {
-    pub fn new() -> Graph {
+    /// An empty search tree over `num_actors` actors, the number of actors
+    /// with credits in the data.
+    pub fn new(num_actors: usize) -> Graph {
         Graph {
-            frontier: Vec::new(),
-            explored: Vec::new(),
+            frontier: VecDeque::new(),
+            visited: vec![UNVISITED; num_actors],
             nodes: Vec::new(),
         }
     }
 
-    pub fn add_node(&mut self, node: Node) {
-        let max_index = self.nodes.len();
+    /// Adds the node for the actor at `actor_index` to the arena and queues
+    /// it on the frontier.
+    ///
+    /// Returns `None` without adding anything if the actor has been reached
+    /// already, so every actor is queued at most once.
+    pub fn add_node(&mut self, actor_index: ActorIndex, node: Node) -> Option<NodeIndex> {
+        let visited = &mut self.visited[actor_index as usize];
+        if *visited != UNVISITED {
+            return None;
+        }
+        let node_index = self.nodes.len();
+        *visited = node_index as u32;
         self.nodes.push(node);
-        self.frontier.push(max_index);
+        self.frontier.push_back(node_index);
+        Some(node_index)
     }
 
-    pub fn add_nodes(&mut self, nodes: Vec<Node>) {
-        for node in nodes {
-            self.add_node(node);
+    /// Index of the node that reached the actor at `actor_index`, if it has
+    /// been visited.
+    pub fn node_index(&self, actor_index: ActorIndex) -> Option<NodeIndex> {
+        match self.visited[actor_index as usize] {
+            UNVISITED => None,
+            node_index => Some(node_index as NodeIndex),
         }
     }
 
-    pub fn get_neighbours(&self, node_index: NodeIndex, movie_db: &MovieDB) -> Vec<Node> {
+    /// Adds a node for every co-star of the node's actor that has not been
+    /// visited yet, through the movies and actors that `options` allows.
+    ///
+    /// Each actor is added once, through the first shared movie found.
+    /// Returns the actor index and node index of every new node.
+    pub fn add_neighbours(
+        &mut self,
+        node_index: NodeIndex,
+        movie_db: &MovieDB,
+        options: &SearchOptions,
+    ) -> Vec<(ActorIndex, NodeIndex)> {
+        self.add_neighbours_where(node_index, movie_db, options, |_, _, _| true)
+    }
+
+    /// Like [`Graph::add_neighbours`], but only follows the hops
+    /// `(actor, movie, co_star)` for which `allowed` returns true.
+    pub fn add_neighbours_where<F>(
+        &mut self,
+        node_index: NodeIndex,
+        movie_db: &MovieDB,
+        options: &SearchOptions,
+        allowed: F,
+    ) -> Vec<(ActorIndex, NodeIndex)>
+    where
+        F: Fn(ActorId, MovieId, ActorId) -> bool,
+    {
         let parent_actor_id = self.nodes[node_index].actor_id;
-        let mut neighbours = Vec::new();
-        // get movies of actor
-        let movies_of_actor = movie_db
-            .actor_to_movies
-            .get(&parent_actor_id)
-            .expect("actor not found");
-        for movie_id in movies_of_actor {
-            // get actors of movie
-            let actors_of_movie = movie_db
-                .movie_to_actors
-                .get(movie_id)
-                .expect("movie not found");
-            for actor_id in actors_of_movie {
-                // continue if actor is same as parent
-                if self.explored.contains(actor_id) {
-                    //assuming parent is in explored
+        let mut added = Vec::new();
+        let connections = &movie_db.connections;
+        let Some(parent_index) = connections.actor_index(parent_actor_id) else {
+            // actor has no credits
+            return added;
+        };
+        for &movie_index in connections.movies_of(parent_index) {
+            if !options.allows_movie(movie_index, movie_db) {
+                continue;
+            }
+            let movie_id = connections.movie_id(movie_index);
+            for &co_star_index in connections.cast_of(movie_index) {
+                // skip the parent, visited actors and co-stars from an
+                // earlier movie, which are all marked already
+                if self.visited[co_star_index as usize] != UNVISITED {
+                    continue;
+                }
+                let actor_id = connections.actor_id(co_star_index);
+                if !options.allows_actor(actor_id) {
+                    continue;
+                }
+                if !allowed(parent_actor_id, movie_id, actor_id) {
                     continue;
                 }
-                let node = Node::new(*actor_id, Some(node_index), Some(*movie_id));
-                neighbours.push(node);
+                let node = Node::new(actor_id, Some(node_index), Some(movie_id));
+                if let Some(new_index) = self.add_node(co_star_index, node) {
+                    added.push((co_star_index, new_index));
+                }
             }
         }
-        neighbours
+        added
     }
 
     pub fn get_path_to_root(&self, mut node_index: NodeIndex) -> Vec<Node> {
@@ -101,55 +374,278 @@ impl Graph {
     }
 }
 
+/// Finds a shortest chain of co-stars between two actors.
+///
+/// The search grows one BFS tree from the source and one from the target,
+/// always expanding a whole level of whichever tree has the smaller frontier,
+/// and stops as soon as the two trees touch. The returned path starts at the
+/// source and ends at the target; each node after the first carries the movie
+/// linking it to the previous actor. The path only goes through the actors
+/// and movies that `options` allows.
+///
+/// Gives up with [`SearchError::BudgetExceeded`] when the path would be
+/// longer than `limits` allows or the search runs out of actors or time, and
+/// with [`SearchError::Cancelled`] once its cancellation token is cancelled.
+///
+/// Returns the path with how much work finding it took. The same stats are
+/// recorded on the `shortest_path` tracing span, whatever the outcome.
+#[tracing::instrument(
+    skip(movie_db, options, limits),
+    fields(
+        nodes_created = field::Empty,
+        nodes_explored = field::Empty,
+        forward_frontier_sizes = field::Empty,
+        backward_frontier_sizes = field::Empty,
+        elapsed_ms = field::Empty,
+    )
+)]
 pub async fn shortest_path(
     source_actor_id: ActorId,
     target_actor_id: ActorId,
     movie_db: &MovieDB,
-) -> Result<Vec<Node>, &'static str> {
-    // make empty new graph
-    let mut graph = Graph::new();
-    // add source node to frontier
-    let source_node = Node::new(source_actor_id, None, None);
-    graph.add_node(source_node);
-    let mut num_explored: usize = 0;
-    while !graph.frontier.is_empty() {
-        let node_index = graph.frontier.remove(0);
-        let node = &graph.nodes[node_index];
-        // add current node actor id to explored list
-        graph.explored.push(node.actor_id);
+    options: &SearchOptions,
+    limits: &SearchLimits,
+) -> Result<(Vec<Node>, SearchStats), SearchError> {
+    let (result, stats) = bidirectional_search(
+        &[source_actor_id],
+        &[target_actor_id],
+        movie_db,
+        options,
+        limits,
+        |_, _, _| true,
+    )
+    .await;
+    stats.record(&Span::current());
+    result.map(|path| (path, stats))
+}
+
+/// Finds which of `target_actor_ids` is the fewest hops away from the source,
+/// and a shortest chain of co-stars to it.
+///
+/// Works like [`shortest_path`], with the backward search growing from all
+/// the targets at once, so it costs about as much as a single search. When
+/// several targets are equally near, one of them is returned. Targets that
+/// `options` excludes are ignored.
+pub async fn nearest_actor(
+    source_actor_id: ActorId,
+    target_actor_ids: &[ActorId],
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+) -> Result<(ActorId, Vec<Node>), SearchError> {
+    let path = bidirectional_search(
+        &[source_actor_id],
+        target_actor_ids,
+        movie_db,
+        options,
+        &SearchLimits::default(),
+        |_, _, _| true,
+    )
+    .await
+    .0?;
+    Ok((path.last().unwrap().actor_id, path))
+}
+
+/// The search behind [`shortest_path`] and [`nearest_actor`], from the
+/// nearest of the sources to the nearest of the targets, only following the
+/// hops `(actor, movie, co_star)` for which `allowed` returns true.
+///
+/// Hops are followed in both directions, so `allowed` should not depend on
+/// which of the two actors comes first.
+async fn bidirectional_search<F>(
+    source_actor_ids: &[ActorId],
+    target_actor_ids: &[ActorId],
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+    allowed: F,
+) -> (Result<Vec<Node>, SearchError>, SearchStats)
+where
+    F: Fn(ActorId, MovieId, ActorId) -> bool,
+{
+    let start = Instant::now();
+    let mut stats = SearchStats::default();
+    let result = grow_trees(
+        source_actor_ids,
+        target_actor_ids,
+        movie_db,
+        options,
+        limits,
+        allowed,
+        &mut stats,
+    )
+    .await;
+    stats.elapsed = start.elapsed();
+    (result, stats)
+}
+
+/// Body of [`bidirectional_search`], counting its work in `stats`.
+async fn grow_trees<F>(
+    source_actor_ids: &[ActorId],
+    target_actor_ids: &[ActorId],
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+    allowed: F,
+    stats: &mut SearchStats,
+) -> Result<Vec<Node>, SearchError>
+where
+    F: Fn(ActorId, MovieId, ActorId) -> bool,
+{
+    let sources: Vec<ActorId> = source_actor_ids
+        .iter()
+        .copied()
+        .filter(|&actor_id| options.allows_actor(actor_id))
+        .collect();
+    // targets in another component can't be reached whatever the options
+    let targets: Vec<ActorId> = target_actor_ids
+        .iter()
+        .copied()
+        .filter(|&actor_id| options.allows_actor(actor_id))
+        .filter(|&actor_id| {
+            sources
+                .iter()
+                .any(|&source| movie_db.connected(source, actor_id))
+        })
+        .collect();
+    if let Some(&source) = sources.iter().find(|source| targets.contains(source)) {
+        return Ok(vec![Node::new(source, None, None)]);
+    }
+    // one search tree rooted at every source, one rooted at every target;
+    // actors without credits have no co-stars to expand
+    let connections = &movie_db.connections;
+    let mut forward = Graph::new(connections.num_actors());
+    for source_actor_id in sources {
+        if let Some(actor_index) = connections.actor_index(source_actor_id) {
+            forward.add_node(actor_index, Node::new(source_actor_id, None, None));
+        }
+    }
+    let mut backward = Graph::new(connections.num_actors());
+    for target_actor_id in targets {
+        if let Some(actor_index) = connections.actor_index(target_actor_id) {
+            backward.add_node(actor_index, Node::new(target_actor_id, None, None));
+        }
+    }
+
+    while !forward.frontier.is_empty() && !backward.frontier.is_empty() {
+        // without a meeting, the path is longer than the levels expanded on
+        // both sides together
+        if limits.max_depth.is_some_and(|max_depth| {
+            stats.forward_frontier_sizes.len() + stats.backward_frontier_sizes.len() >= max_depth
+        }) {
+            return Err(SearchError::BudgetExceeded(Budget::Depth));
+        }
+        let expand_forward = forward.frontier.len() <= backward.frontier.len();
+        let (expanding, other) = if expand_forward {
+            stats.forward_frontier_sizes.push(forward.frontier.len());
+            (&mut forward, &backward)
+        } else {
+            stats.backward_frontier_sizes.push(backward.frontier.len());
+            (&mut backward, &forward)
+        };
+
+        let meeting = expand_level(
+            expanding,
+            other,
+            movie_db,
+            options,
+            limits,
+            &allowed,
+            &mut stats.nodes_explored,
+        )
+        .await;
+        stats.nodes_created = forward.nodes.len() + backward.nodes.len();
+        if let Some((node_index, other_index)) = meeting? {
+            let (forward_index, backward_index) = if expand_forward {
+                (node_index, other_index)
+            } else {
+                (other_index, node_index)
+            };
+            return Ok(join_paths(
+                forward.get_path_to_root(forward_index),
+                backward.get_path_to_root(backward_index),
+            ));
+        }
+    }
+    Err(SearchError::NoPath)
+}
 
+/// Expands every node of the current frontier of `graph` by one hop.
+///
+/// Returns the index of the new node in `graph` and of the node in `other`
+/// for the same actor as soon as the two trees meet.
+async fn expand_level<F>(
+    graph: &mut Graph,
+    other: &Graph,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+    allowed: &F,
+    num_explored: &mut usize,
+) -> Result<Option<(NodeIndex, NodeIndex)>, SearchError>
+where
+    F: Fn(ActorId, MovieId, ActorId) -> bool,
+{
+    let level_size = graph.frontier.len();
+    for _ in 0..level_size {
+        let Some(node_index) = graph.frontier.pop_front() else {
+            break;
+        };
         // yield control to event loop
         if num_explored.is_multiple_of(1000) {
             tokio::task::yield_now().await;
+            limits.check_deadline()?;
         }
+        limits.check(*num_explored)?;
+        *num_explored += 1;
 
-        // get neighbours of node
-        let neighbours = graph.get_neighbours(node_index, movie_db);
-        // check if any neighbour is target
-        if let Some(neighbour) = neighbours
-            .iter()
-            .find(|neighbour| neighbour.actor_id == target_actor_id)
+        for (actor_index, new_index) in
+            graph.add_neighbours_where(node_index, movie_db, options, allowed)
         {
-            // add this node to the arena
-            graph.add_node(neighbour.clone());
-            println!("nodes created: {}", graph.nodes.len());
-            println!("nodes explored: {}", num_explored);
-            let path = graph.get_path_to_root(graph.nodes.len() - 1);
-            return Ok(path);
+            if let Some(other_index) = other.node_index(actor_index) {
+                return Ok(Some((new_index, other_index)));
+            }
         }
+    }
+    Ok(None)
+}
+
+/// Joins a path from the source to the meeting actor with a path from the
+/// target to the same actor into one path from source to target.
+fn join_paths(forward_path: Vec<Node>, backward_path: Vec<Node>) -> Vec<Node> {
+    let mut path = forward_path;
+    // walking the backward path in reverse, each actor is reached through
+    // the movie stored on the node after it
+    for pair in backward_path.windows(2).rev() {
+        path.push(Node::new(pair[0].actor_id, None, pair[1].movie_id));
+    }
+    index_path(&mut path);
+    path
+}
+
+/// The same path walked from its last actor back to its first.
+pub fn reverse_path(path: &[Node]) -> Vec<Node> {
+    match path.last() {
+        Some(last) => join_paths(vec![Node::new(last.actor_id, None, None)], path.to_vec()),
+        None => Vec::new(),
+    }
+}
 
-        graph.add_nodes(neighbours);
-        num_explored += 1;
+/// Points the parent index of every node at the node before it, so parent
+/// indices refer to positions in the path itself.
+fn index_path(path: &mut [Node]) {
+    for (index, node) in path.iter_mut().enumerate() {
+        node.parent_index = index.checked_sub(1);
     }
-    Err("no path found")
 }
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
-    use crate::data::{MovieDB, MovieDBBuilder};
+    use crate::data::synthetic::random_credits;
+    use crate::data::{ActorIndex, MovieDB, MovieDBBuilder};
+    use std::collections::HashMap;
     use std::path::PathBuf;
 
-    fn make_test_db() -> MovieDB {
+    pub(crate) fn make_test_db() -> MovieDB {
         let data_dir = PathBuf::from("data/new_small");
         let actor_file = data_dir.join("actors.csv");
         let actors = MovieDBBuilder::read_actors(&actor_file).unwrap();
@@ -157,37 +653,98 @@ mod test {
         let movies = MovieDBBuilder::read_movies(&movie_file).unwrap();
         let actor_movie_file = data_dir.join("connections.csv");
         let pairs = MovieDBBuilder::read_actor_movie_pairs(&actor_movie_file).unwrap();
-        let (actor_to_movies, movie_to_actors) = MovieDBBuilder::get_actor_movie_maps(pairs);
+        let connections = MovieDBBuilder::get_connections(pairs);
         MovieDB {
             actors,
             movies,
-            actor_to_movies,
-            movie_to_actors,
+            connections,
+        }
+    }
+
+    /// A db of the `(actor_id, movie_id)` credits, with no names or titles.
+    pub(crate) fn make_db(pairs: Vec<(usize, usize)>) -> MovieDB {
+        MovieDB {
+            connections: MovieDBBuilder::get_connections(pairs),
+            actors: HashMap::new(),
+            movies: HashMap::new(),
+        }
+    }
+
+    /// A random co-star graph of `num_actors` actors in movies of two to
+    /// four, sparse enough for paths of several hops.
+    pub(crate) fn make_random_db(num_actors: usize) -> MovieDB {
+        make_db(random_credits(0, num_actors, 4))
+    }
+
+    /// Ids of the actors along the path.
+    pub(crate) fn actor_ids(path: &[Node]) -> Vec<usize> {
+        path.iter().map(|node| node.actor_id).collect()
+    }
+
+    /// Ids of every actor with credits.
+    pub(crate) fn all_actor_ids(movie_db: &MovieDB) -> Vec<ActorId> {
+        let connections = &movie_db.connections;
+        (0..connections.num_actors() as ActorIndex)
+            .map(|actor| connections.actor_id(actor))
+            .collect()
+    }
+
+    /// Checks that every hop of the path is through a movie both actors
+    /// were in.
+    pub(crate) fn assert_chain(path: &[Node], movie_db: &MovieDB) {
+        for hop in path.windows(2) {
+            let movie_id = hop[1].movie_id.unwrap();
+            let cast: Vec<usize> = movie_db.actors_of_movie(movie_id).collect();
+            assert!(cast.contains(&hop[0].actor_id));
+            assert!(cast.contains(&hop[1].actor_id));
         }
     }
 
     #[test]
     fn adding_nodes() {
-        let mut graph = Graph::new();
+        // actor ids double as actor indices
+        let mut graph = Graph::new(4);
         let node1 = Node::new(1, None, None);
-        graph.add_node(node1.clone());
+        graph.add_node(1, node1.clone());
         let node2 = Node::new(2, Some(0), Some(8));
-        graph.add_node(node2.clone());
+        graph.add_node(2, node2.clone());
         let node3 = Node::new(3, Some(1), Some(7));
-        graph.add_node(node3.clone());
+        graph.add_node(3, node3.clone());
 
         assert_eq!(graph.nodes, vec![node1, node2, node3]);
         assert_eq!(graph.frontier, vec![0, 1, 2])
     }
+    #[test]
+    fn reversed_path() {
+        let path = vec![
+            Node::new(129, None, None),
+            Node::new(102, Some(0), Some(104257)),
+            Node::new(158, Some(1), Some(112384)),
+        ];
+        let reversed = reverse_path(&path);
+        assert_eq!(
+            reversed,
+            vec![
+                Node::new(158, None, None),
+                Node::new(102, Some(0), Some(112384)),
+                Node::new(129, Some(1), Some(104257)),
+            ]
+        );
+        assert_eq!(reversed[2].parent_index, Some(1));
+        assert_eq!(reverse_path(&reversed), path);
+        assert!(reverse_path(&[]).is_empty());
+    }
+
     #[test]
     fn graph_path_to_root() {
-        let mut graph = Graph::new();
+        // actor ids double as actor indices
+        let mut graph = Graph::new(4);
         let node1 = Node::new(1, None, None);
-        graph.add_node(node1.clone());
+        graph.add_node(1, node1.clone());
         let node2 = Node::new(2, Some(0), Some(8));
-        graph.add_node(node2.clone());
+        graph.add_node(2, node2.clone());
         let node3 = Node::new(3, Some(1), Some(7));
-        graph.add_node(node3.clone());
+        graph.add_node(3, node3.clone());
 
         let path = graph.get_path_to_root(2);
         assert_eq!(path, vec![node1, node2, node3]);
@@ -196,22 +753,54 @@ mod test {
     #[test]
     fn test_neighbours() {
         let db = make_test_db();
-        let mut graph = Graph::new();
+        let connections = &db.connections;
+        let mut graph = Graph::new(connections.num_actors());
         let tom_cruise = Node::new(129, None, None);
-        graph.explored.push(tom_cruise.actor_id);
-        graph.add_node(tom_cruise);
+        graph.add_node(connections.actor_index(129).unwrap(), tom_cruise);
 
-        let neighbours = graph.get_neighbours(0, &db); // Tbom Cruise
+        let neighbours = graph.add_neighbours(0, &db, &SearchOptions::default()); // Tbom Cruise
 
         let mut neighbour_ids = neighbours
             .iter()
-            .map(|node| node.actor_id)
+            .map(|&(actor_index, node_index)| {
+                assert_eq!(graph.node_index(actor_index), Some(node_index));
+                graph.nodes[node_index].actor_id
+            })
             .collect::<Vec<usize>>();
         neighbour_ids.sort();
 
         assert_eq!(neighbour_ids, vec![102, 163, 193, 197, 420, 596520]);
     }
 
+    #[test]
+    fn adding_visited_actor() {
+        let mut graph = Graph::new(2);
+        assert_eq!(graph.add_node(1, Node::new(1, None, None)), Some(0));
+        assert_eq!(graph.add_node(1, Node::new(1, Some(0), Some(8))), None);
+        assert_eq!(graph.node_index(1), Some(0));
+        assert_eq!(graph.node_index(0), None);
+        assert_eq!(graph.frontier, vec![0]);
+    }
+
+    #[test]
+    fn test_neighbours_unique() {
+        let db = make_test_db();
+        let connections = &db.connections;
+        let mut graph = Graph::new(connections.num_actors());
+        let tom_hanks = connections.actor_index(158).unwrap();
+        graph.add_node(tom_hanks, Node::new(158, None, None));
+
+        // Gary Sinise shares both Forrest Gump and Apollo 13 with Tom Hanks
+        let neighbours = graph.add_neighbours(0, &db, &SearchOptions::default());
+        let mut neighbour_ids = neighbours
+            .iter()
+            .map(|&(_, node_index)| graph.nodes[node_index].actor_id)
+            .collect::<Vec<usize>>();
+        neighbour_ids.sort();
+
+        assert_eq!(neighbour_ids, vec![102, 200, 398, 641, 705]);
+    }
+
     #[tokio::test]
     async fn test_shortest_path_cruise_nicholson() {
         let db = make_test_db();
@@ -221,8 +810,18 @@ mod test {
         let tom_cruise = Node::new(source_id, None, None);
         let jack_nicholson = Node::new(target_id, Some(0), Some(104257));
 
-        let path = shortest_path(129, target_id, &db).await;
-        assert_eq!(path, Ok(vec![tom_cruise, jack_nicholson]));
+        let path = shortest_path(
+            129,
+            target_id,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await;
+        assert_eq!(
+            path.map(|(path, _)| path),
+            Ok(vec![tom_cruise, jack_nicholson])
+        );
     }
 
     #[tokio::test]
@@ -235,7 +834,324 @@ mod test {
         let connector = Node::new(102, Some(0), Some(104257));
         let hanks = Node::new(target_id, Some(102), Some(112384));
 
-        let path = shortest_path(source_id, target_id, &db).await;
-        assert_eq!(path, Ok(vec![tom_cruise, connector, hanks]));
+        let path = shortest_path(
+            source_id,
+            target_id,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await;
+        assert_eq!(
+            path.map(|(path, _)| path),
+            Ok(vec![tom_cruise, connector, hanks])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_stats() {
+        let db = make_test_db();
+        let (path, stats) = shortest_path(
+            129,
+            158,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(path.len(), 3);
+        // two hops take one level from each end, or two from one of them
+        assert_eq!(
+            stats.forward_frontier_sizes.len() + stats.backward_frontier_sizes.len(),
+            2
+        );
+        assert!(stats.nodes_explored > 0);
+        assert!(stats.nodes_created >= path.len());
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_same_actor() {
+        let db = make_test_db();
+        let path = shortest_path(
+            129,
+            129,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await;
+        assert_eq!(
+            path.map(|(path, _)| path),
+            Ok(vec![Node::new(129, None, None)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_elwes_nicholson() {
+        let db = make_test_db();
+        let source_id = 144; // Cary Elwes
+        let target_id = 197; // Jack Nicholson
+
+        let path = shortest_path(
+            source_id,
+            target_id,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap()
+        .0;
+        assert_eq!(path.len(), 5);
+        assert_eq!(path.first().unwrap().actor_id, source_id);
+        assert_eq!(path.last().unwrap().actor_id, target_id);
+        // every hop is a movie both actors were in
+        for pair in path.windows(2) {
+            let cast: Vec<usize> = db.actors_of_movie(pair[1].movie_id.unwrap()).collect();
+            assert!(cast.contains(&pair[0].actor_id));
+            assert!(cast.contains(&pair[1].actor_id));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_excluded_actor() {
+        let db = make_test_db();
+        let source_id = 144; // Cary Elwes
+        let target_id = 102; // Kevin Bacon
+
+        // around Tom Hanks through Gary Sinise
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([158]),
+            ..Default::default()
+        };
+        let path = shortest_path(
+            source_id,
+            target_id,
+            &db,
+            &options,
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap()
+        .0;
+        let actor_ids: Vec<usize> = path.iter().map(|node| node.actor_id).collect();
+        assert_eq!(actor_ids, vec![144, 705, 641, 102]);
+
+        // Tom Cruise only reaches Tom Hanks through Kevin Bacon
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([102]),
+            ..Default::default()
+        };
+        let path = shortest_path(129, 158, &db, &options, &SearchLimits::default()).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+
+        // an excluded end
+        let path = shortest_path(102, 129, &db, &options, &SearchLimits::default()).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_excluded_movie() {
+        let db = make_test_db();
+        // Tom Hanks and Kevin Bacon only share Apollo 13
+        let options = SearchOptions {
+            excluded_movies: HashSet::from([112384]),
+            ..Default::default()
+        };
+        let path = shortest_path(158, 102, &db, &options, &SearchLimits::default()).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_max_cast_size() {
+        let db = make_test_db();
+        // every movie in the small dataset has a cast of four
+        let mut options = SearchOptions {
+            max_cast_size: Some(3),
+            ..Default::default()
+        };
+        let path = shortest_path(158, 102, &db, &options, &SearchLimits::default()).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+
+        options.max_cast_size = Some(4);
+        let path = shortest_path(158, 102, &db, &options, &SearchLimits::default())
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(path.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_max_year() {
+        let db = make_test_db();
+        // Cary Elwes and Tom Hanks are linked through Robin Wright from
+        // Forrest Gump (1994) on
+        let mut options = SearchOptions {
+            max_year: Some(1993),
+            ..Default::default()
+        };
+        let path = shortest_path(144, 158, &db, &options, &SearchLimits::default()).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+
+        options.max_year = Some(1994);
+        let path = shortest_path(144, 158, &db, &options, &SearchLimits::default())
+            .await
+            .unwrap()
+            .0;
+        let actor_ids: Vec<usize> = path.iter().map(|node| node.actor_id).collect();
+        assert_eq!(actor_ids, vec![144, 705, 158]);
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_year_range() {
+        let db = make_test_db();
+        // Gary Sinise and Tom Hanks were in Forrest Gump (1994) and Apollo 13
+        // (1995) together
+        let mut options = SearchOptions {
+            max_year: Some(1994),
+            ..Default::default()
+        };
+        let path = shortest_path(641, 158, &db, &options, &SearchLimits::default())
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(path[1].movie_id, Some(109830));
+
+        options.min_year = Some(1995);
+        let path = shortest_path(641, 158, &db, &options, &SearchLimits::default()).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+
+        options.max_year = None;
+        let path = shortest_path(641, 158, &db, &options, &SearchLimits::default())
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(path[1].movie_id, Some(112384));
+
+        // Tom Cruise only reaches Tom Hanks through Apollo 13
+        let path = shortest_path(129, 158, &db, &options, &SearchLimits::default()).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+    }
+
+    #[tokio::test]
+    async fn test_nearest_actor() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        // Kevin Bacon is one hop from Tom Cruise, Tom Hanks two and Cary
+        // Elwes four
+        let (nearest, path) = nearest_actor(129, &[144, 158, 102], &db, &options)
+            .await
+            .unwrap();
+        assert_eq!(nearest, 102);
+        assert_eq!(path.len(), 2);
+
+        let (nearest, path) = nearest_actor(129, &[144, 158], &db, &options)
+            .await
+            .unwrap();
+        assert_eq!(nearest, 158);
+        assert_eq!(
+            path,
+            shortest_path(129, 158, &db, &options, &SearchLimits::default())
+                .await
+                .unwrap()
+                .0
+        );
+
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([158]),
+            ..Default::default()
+        };
+        let (nearest, path) = nearest_actor(129, &[144, 158], &db, &options)
+            .await
+            .unwrap();
+        assert_eq!(nearest, 144);
+        assert_eq!(path.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_nearest_actor_edge_cases() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        let (nearest, path) = nearest_actor(129, &[158, 129], &db, &options)
+            .await
+            .unwrap();
+        assert_eq!(nearest, 129);
+        assert_eq!(path.len(), 1);
+
+        let nearest = nearest_actor(129, &[], &db, &options).await;
+        assert_eq!(nearest, Err(SearchError::NoPath));
+        // an actor id without any credits
+        let nearest = nearest_actor(129, &[1], &db, &options).await;
+        assert_eq!(nearest, Err(SearchError::NoPath));
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_no_credits() {
+        let db = make_test_db();
+        // an actor id without any credits
+        let path = shortest_path(
+            129,
+            1,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await;
+        assert_eq!(path, Err(SearchError::NoPath));
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_limits() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        // Tom Cruise and Tom Hanks are two hops apart
+        let limits = SearchLimits {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let path = shortest_path(129, 158, &db, &options, &limits).await;
+        assert_eq!(path.unwrap().0.len(), 3);
+        let limits = SearchLimits {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let path = shortest_path(129, 158, &db, &options, &limits).await;
+        assert_eq!(path, Err(SearchError::BudgetExceeded(Budget::Depth)));
+
+        let limits = SearchLimits {
+            max_nodes_explored: Some(1),
+            ..Default::default()
+        };
+        let path = shortest_path(129, 158, &db, &options, &limits).await;
+        assert_eq!(
+            path,
+            Err(SearchError::BudgetExceeded(Budget::NodesExplored))
+        );
+
+        let limits = SearchLimits {
+            deadline: Some(Instant::now()),
+            ..Default::default()
+        };
+        let path = shortest_path(129, 158, &db, &options, &limits).await;
+        assert_eq!(path, Err(SearchError::BudgetExceeded(Budget::Deadline)));
+
+        // limits don't turn a missing path into a budget error
+        let limits = SearchLimits {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let path = shortest_path(129, 1, &db, &options, &limits).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_cancelled() {
+        let db = make_test_db();
+        let limits = SearchLimits::default();
+        limits.cancellation.cancel();
+        let path = shortest_path(129, 158, &db, &SearchOptions::default(), &limits).await;
+        assert_eq!(path, Err(SearchError::Cancelled));
+        assert_eq!(SearchError::Cancelled.to_string(), "search was cancelled");
     }
 }
@@ -0,0 +1,359 @@
+use super::{ActorId, Budget, Node, SearchError, SearchLimits, SearchOptions, join_paths};
+use crate::data::{ActorIndex, Connections, MovieDB, MovieIndex};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+/// One side of the bidirectional search: a BFS DAG that keeps every parent
+/// reaching an actor at its minimal depth, not just the first one.
+struct Dag {
+    depth: HashMap<ActorIndex, u32>,
+    parents: HashMap<ActorIndex, Vec<(ActorIndex, MovieIndex)>>,
+    frontier: Vec<ActorIndex>,
+    level: u32,
+}
+
+impl Dag {
+    fn new(root: ActorIndex) -> Dag {
+        Dag {
+            depth: HashMap::from([(root, 0)]),
+            parents: HashMap::new(),
+            frontier: vec![root],
+            level: 0,
+        }
+    }
+
+    /// Expands the whole frontier by one hop and returns the actors of the
+    /// new level that `other` has reached too.
+    async fn expand_level(
+        &mut self,
+        other: &Dag,
+        movie_db: &MovieDB,
+        options: &SearchOptions,
+        limits: &SearchLimits,
+        num_explored: &mut usize,
+    ) -> Result<Vec<ActorIndex>, SearchError> {
+        let connections = &movie_db.connections;
+        let next_level = self.level + 1;
+        let mut next_frontier = Vec::new();
+        for actor in std::mem::take(&mut self.frontier) {
+            // yield control to event loop
+            if num_explored.is_multiple_of(1000) {
+                tokio::task::yield_now().await;
+                limits.check_deadline()?;
+            }
+            limits.check(*num_explored)?;
+            *num_explored += 1;
+
+            for &movie in connections.movies_of(actor) {
+                if !options.allows_movie(movie, movie_db) {
+                    continue;
+                }
+                for &co_star in connections.cast_of(movie) {
+                    if !options.allows_actor(connections.actor_id(co_star)) {
+                        continue;
+                    }
+                    match self.depth.entry(co_star) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(next_level);
+                            self.parents.insert(co_star, vec![(actor, movie)]);
+                            next_frontier.push(co_star);
+                        }
+                        // another parent, or another movie with the same parent
+                        Entry::Occupied(entry) if *entry.get() == next_level => {
+                            self.parents.get_mut(&co_star).unwrap().push((actor, movie));
+                        }
+                        Entry::Occupied(_) => {}
+                    }
+                }
+            }
+        }
+        self.level = next_level;
+        self.frontier = next_frontier;
+
+        let mut meeting: Vec<ActorIndex> = self
+            .frontier
+            .iter()
+            .copied()
+            .filter(|actor| other.depth.contains_key(actor))
+            .collect();
+        meeting.sort_unstable();
+        Ok(meeting)
+    }
+
+    /// Up to `limit` chains from the root of the DAG to `actor`.
+    fn chains_to(
+        &self,
+        actor: ActorIndex,
+        limit: usize,
+        connections: &Connections,
+    ) -> Vec<Vec<Node>> {
+        let mut chains = Vec::new();
+        self.collect_chains(actor, &mut Vec::new(), &mut chains, limit, connections);
+        chains
+    }
+
+    /// Walks up every parent of `actor`. `suffix` holds the nodes below
+    /// `actor` on the chain being built, nearest last.
+    fn collect_chains(
+        &self,
+        actor: ActorIndex,
+        suffix: &mut Vec<Node>,
+        chains: &mut Vec<Vec<Node>>,
+        limit: usize,
+        connections: &Connections,
+    ) {
+        let actor_id = connections.actor_id(actor);
+        let Some(parents) = self.parents.get(&actor) else {
+            // reached the root
+            let mut chain = vec![Node::new(actor_id, None, None)];
+            chain.extend(suffix.iter().rev().cloned());
+            chains.push(chain);
+            return;
+        };
+        for &(parent, movie) in parents {
+            if chains.len() >= limit {
+                return;
+            }
+            let movie_id = connections.movie_id(movie);
+            suffix.push(Node::new(actor_id, None, Some(movie_id)));
+            self.collect_chains(parent, suffix, chains, limit, connections);
+            suffix.pop();
+        }
+    }
+}
+
+/// Finds every distinct shortest chain of co-stars between two actors, or
+/// the first `max_paths` of them.
+///
+/// Chains are distinct when they differ in an actor or in a movie linking two
+/// actors. Like [`super::shortest_path`] the search grows from both ends, but
+/// it finishes the level where the two sides meet and keeps every parent at
+/// the minimal depth, so every shortest chain passes through one of the
+/// meeting actors. Paths come out in a stable order, and only go through the
+/// actors and movies that `options` allows. The search gives up once it runs
+/// into `limits`.
+pub async fn all_shortest_paths(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    max_paths: Option<usize>,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+) -> Result<Vec<Vec<Node>>, SearchError> {
+    let limit = max_paths.unwrap_or(usize::MAX);
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+    if !options.allows_actor(source_actor_id) || !options.allows_actor(target_actor_id) {
+        return Err(SearchError::NoPath);
+    }
+    if source_actor_id == target_actor_id {
+        return Ok(vec![vec![Node::new(source_actor_id, None, None)]]);
+    }
+    if !movie_db.connected(source_actor_id, target_actor_id) {
+        return Err(SearchError::NoPath);
+    }
+    let connections = &movie_db.connections;
+    let (Some(source), Some(target)) = (
+        connections.actor_index(source_actor_id),
+        connections.actor_index(target_actor_id),
+    ) else {
+        // one of the actors has no credits
+        return Err(SearchError::NoPath);
+    };
+
+    let mut forward = Dag::new(source);
+    let mut backward = Dag::new(target);
+    let mut num_explored: usize = 0;
+    while !forward.frontier.is_empty() && !backward.frontier.is_empty() {
+        // without a meeting, the paths are longer than the levels expanded
+        // on both sides together
+        if limits
+            .max_depth
+            .is_some_and(|max_depth| (forward.level + backward.level) as usize >= max_depth)
+        {
+            return Err(SearchError::BudgetExceeded(Budget::Depth));
+        }
+        let expand_forward = forward.frontier.len() <= backward.frontier.len();
+        let meeting = if expand_forward {
+            forward
+                .expand_level(&backward, movie_db, options, limits, &mut num_explored)
+                .await?
+        } else {
+            backward
+                .expand_level(&forward, movie_db, options, limits, &mut num_explored)
+                .await?
+        };
+        if meeting.is_empty() {
+            continue;
+        }
+
+        let mut paths = Vec::new();
+        for actor in meeting {
+            let remaining = limit - paths.len();
+            let forward_chains = forward.chains_to(actor, remaining, connections);
+            let backward_chains = backward.chains_to(actor, remaining, connections);
+            for forward_chain in &forward_chains {
+                for backward_chain in &backward_chains {
+                    paths.push(join_paths(forward_chain.clone(), backward_chain.clone()));
+                    if paths.len() == limit {
+                        return Ok(paths);
+                    }
+                }
+            }
+        }
+        return Ok(paths);
+    }
+    Err(SearchError::NoPath)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::MovieDBBuilder;
+    use crate::graph::shortest_path;
+    use crate::graph::test::{actor_ids, make_test_db};
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn all_paths_cruise_hanks() {
+        let db = make_test_db();
+        // Tom Cruise and Tom Hanks are only linked through Kevin Bacon
+        let paths = all_shortest_paths(
+            129,
+            158,
+            None,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(actor_ids(&paths[0]), vec![129, 102, 158]);
+        assert_eq!(paths[0][1].movie_id, Some(104257));
+        assert_eq!(paths[0][2].movie_id, Some(112384));
+    }
+
+    #[tokio::test]
+    async fn all_paths_elwes_bacon() {
+        let db = make_test_db();
+        // Cary Elwes -> Robin Wright -> (Tom Hanks | Gary Sinise) -> Kevin Bacon
+        let paths = all_shortest_paths(
+            144,
+            102,
+            None,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        let chains: HashSet<Vec<usize>> = paths.iter().map(|path| actor_ids(path)).collect();
+        assert_eq!(
+            chains,
+            HashSet::from([vec![144, 705, 158, 102], vec![144, 705, 641, 102]])
+        );
+        assert_eq!(paths.len(), 2);
+
+        // the single shortest path is one of them
+        let path = shortest_path(
+            144,
+            102,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap()
+        .0;
+        assert!(chains.contains(&actor_ids(&path)));
+    }
+
+    #[tokio::test]
+    async fn all_paths_limit() {
+        let db = make_test_db();
+        let paths = all_shortest_paths(
+            144,
+            102,
+            Some(1),
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].len(), 4);
+    }
+
+    #[tokio::test]
+    async fn all_paths_distinct_movies() {
+        // two actors who made two movies together give two chains
+        let connections = MovieDBBuilder::get_connections(vec![(1, 10), (2, 10), (1, 11), (2, 11)]);
+        let db = MovieDB {
+            connections,
+            actors: HashMap::new(),
+            movies: HashMap::new(),
+        };
+        let paths = all_shortest_paths(
+            1,
+            2,
+            None,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        let movies: Vec<Option<usize>> = paths.iter().map(|path| path[1].movie_id).collect();
+        assert_eq!(movies, vec![Some(10), Some(11)]);
+    }
+
+    #[tokio::test]
+    async fn all_paths_excluded_actor() {
+        let db = make_test_db();
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([641]), // Gary Sinise
+            ..Default::default()
+        };
+        let paths = all_shortest_paths(144, 102, None, &db, &options, &SearchLimits::default())
+            .await
+            .unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(actor_ids(&paths[0]), vec![144, 705, 158, 102]);
+    }
+
+    #[tokio::test]
+    async fn all_paths_no_path() {
+        let db = make_test_db();
+        let paths = all_shortest_paths(
+            129,
+            1,
+            None,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await;
+        assert_eq!(paths, Err(SearchError::NoPath));
+    }
+
+    #[tokio::test]
+    async fn all_paths_limits() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        // the only path has two hops
+        let limits = SearchLimits {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let paths = all_shortest_paths(129, 158, None, &db, &options, &limits).await;
+        assert_eq!(paths, Err(SearchError::BudgetExceeded(Budget::Depth)));
+
+        let limits = SearchLimits::default();
+        limits.cancellation.cancel();
+        let paths = all_shortest_paths(129, 158, None, &db, &options, &limits).await;
+        assert_eq!(paths, Err(SearchError::Cancelled));
+    }
+}
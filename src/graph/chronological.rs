@@ -0,0 +1,351 @@
+use super::{ActorId, Budget, Node, SearchError, SearchLimits, SearchOptions, index_path};
+use crate::data::{ActorIndex, MovieDB, MovieIndex};
+use std::collections::{HashMap, VecDeque};
+
+/// How the release years along a chronological chain must progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YearOrder {
+    /// Each movie came out in the same year as the previous one, or later.
+    NonDecreasing,
+    /// Each movie came out in a later year than the previous one.
+    Increasing,
+}
+
+impl YearOrder {
+    fn allows(self, latest_year: Option<u32>, year: u32) -> bool {
+        match (self, latest_year) {
+            (_, None) => true,
+            (YearOrder::NonDecreasing, Some(latest_year)) => year >= latest_year,
+            (YearOrder::Increasing, Some(latest_year)) => year > latest_year,
+        }
+    }
+}
+
+/// An actor reached through a chain whose last movie came out in `year`.
+struct State {
+    actor: ActorIndex,
+    year: Option<u32>,
+    hops: usize,
+    parent: Option<usize>,
+    movie: Option<MovieIndex>,
+}
+
+/// Finds a shortest chain of co-stars between two actors in which every
+/// movie was released no earlier than the one before it, or strictly later
+/// with [`YearOrder::Increasing`].
+///
+/// An actor can be worth reaching twice: a longer chain may get there through
+/// an older movie and leave more movies open for the next hop. The search is
+/// a BFS over `(actor, latest year)` states instead, keeping a state only if
+/// no shorter chain reached the same actor by the same year or earlier.
+/// Movies missing from `movie_db.movies` have no known year and are skipped.
+/// The path only goes through the actors and movies that `options` allows,
+/// and the search gives up once it runs into `limits`.
+pub async fn chronological_path(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    order: YearOrder,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+) -> Result<Vec<Node>, SearchError> {
+    if !options.allows_actor(source_actor_id) || !options.allows_actor(target_actor_id) {
+        return Err(SearchError::NoPath);
+    }
+    if source_actor_id == target_actor_id {
+        return Ok(vec![Node::new(source_actor_id, None, None)]);
+    }
+    if !movie_db.connected(source_actor_id, target_actor_id) {
+        return Err(SearchError::NoPath);
+    }
+    let connections = &movie_db.connections;
+    let (Some(source), Some(target)) = (
+        connections.actor_index(source_actor_id),
+        connections.actor_index(target_actor_id),
+    ) else {
+        // one of the actors has no credits
+        return Err(SearchError::NoPath);
+    };
+
+    let mut states = vec![State {
+        actor: source,
+        year: None,
+        hops: 0,
+        parent: None,
+        movie: None,
+    }];
+    // earliest latest-year each actor has been reached with; the source is
+    // reached with no year at all, which beats any other state
+    let mut earliest: HashMap<ActorIndex, Option<u32>> = HashMap::from([(source, None)]);
+    let mut frontier = VecDeque::from([0]);
+    let mut num_explored: usize = 0;
+
+    while let Some(state_index) = frontier.pop_front() {
+        // yield control to event loop
+        if num_explored.is_multiple_of(1000) {
+            tokio::task::yield_now().await;
+            limits.check_deadline()?;
+        }
+        limits.check(num_explored)?;
+        num_explored += 1;
+
+        let State {
+            actor,
+            year: latest_year,
+            hops,
+            ..
+        } = states[state_index];
+        // states come out in order of hops, so every chain left is too long
+        if limits.max_depth.is_some_and(|max_depth| hops >= max_depth) {
+            return Err(SearchError::BudgetExceeded(Budget::Depth));
+        }
+        for &movie in connections.movies_of(actor) {
+            if !options.allows_movie(movie, movie_db) {
+                continue;
+            }
+            let Some(year) = movie_db
+                .movies
+                .get(&connections.movie_id(movie))
+                .map(|movie| movie.year)
+            else {
+                continue;
+            };
+            if !order.allows(latest_year, year) {
+                continue;
+            }
+            for &co_star in connections.cast_of(movie) {
+                if !options.allows_actor(connections.actor_id(co_star)) {
+                    continue;
+                }
+                // a state reached no later, by an older movie or the same
+                // year, leaves every movie this one would open
+                let dominated = match earliest.get(&co_star) {
+                    None => false,
+                    Some(None) => true,
+                    Some(Some(earliest_year)) => *earliest_year <= year,
+                };
+                if dominated {
+                    continue;
+                }
+                earliest.insert(co_star, Some(year));
+                states.push(State {
+                    actor: co_star,
+                    year: Some(year),
+                    hops: hops + 1,
+                    parent: Some(state_index),
+                    movie: Some(movie),
+                });
+                if co_star == target {
+                    return Ok(state_path(&states, states.len() - 1, movie_db));
+                }
+                frontier.push_back(states.len() - 1);
+            }
+        }
+    }
+    Err(SearchError::NoPath)
+}
+
+/// The chain of states from the source to `state_index` as path nodes.
+fn state_path(states: &[State], mut state_index: usize, movie_db: &MovieDB) -> Vec<Node> {
+    let connections = &movie_db.connections;
+    let mut path = Vec::new();
+    loop {
+        let state = &states[state_index];
+        path.push(Node::new(
+            connections.actor_id(state.actor),
+            None,
+            state.movie.map(|movie| connections.movie_id(movie)),
+        ));
+        match state.parent {
+            Some(parent) => state_index = parent,
+            None => break,
+        }
+    }
+    path.reverse();
+    index_path(&mut path);
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::{Movie, MovieDBBuilder};
+    use crate::graph::shortest_path;
+    use crate::graph::test::{actor_ids, make_test_db};
+
+    /// A db from `(actor_id, movie_id)` credits and `(movie_id, year)` pairs.
+    fn make_dated_db(pairs: Vec<(usize, usize)>, years: &[(usize, u32)]) -> MovieDB {
+        let movies = years
+            .iter()
+            .map(|&(id, year)| {
+                let movie = Movie {
+                    id,
+                    title: format!("movie {}", id),
+                    year,
+                };
+                (id, movie)
+            })
+            .collect();
+        MovieDB {
+            connections: MovieDBBuilder::get_connections(pairs),
+            actors: HashMap::new(),
+            movies,
+        }
+    }
+
+    #[tokio::test]
+    async fn chronological_cruise_hanks() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        // A Few Good Men (1992), then Apollo 13 (1995)
+        let path = chronological_path(
+            129,
+            158,
+            YearOrder::Increasing,
+            &db,
+            &options,
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            path,
+            shortest_path(129, 158, &db, &options, &SearchLimits::default())
+                .await
+                .unwrap()
+                .0
+        );
+    }
+
+    #[tokio::test]
+    async fn chronological_one_way() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        // Tom Hanks only made movies after A Few Good Men and Rain Man
+        let path = chronological_path(
+            158,
+            129,
+            YearOrder::NonDecreasing,
+            &db,
+            &options,
+            &SearchLimits::default(),
+        )
+        .await;
+        assert_eq!(path, Err(SearchError::NoPath));
+
+        // The Princess Bride (1987), Forrest Gump (1994), Apollo 13 (1995)
+        let path = chronological_path(
+            144,
+            102,
+            YearOrder::NonDecreasing,
+            &db,
+            &options,
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path[1].movie_id, Some(93779));
+        assert_eq!(path[2].movie_id, Some(109830));
+        assert_eq!(path[3].movie_id, Some(112384));
+    }
+
+    #[tokio::test]
+    async fn chronological_revisits_actor() {
+        // actor 2 is a co-star of 1 in a late movie, but only reaching 2
+        // through 3 and an older movie leaves the way to 4 open
+        let db = make_dated_db(
+            vec![
+                (1, 10),
+                (2, 10),
+                (1, 11),
+                (3, 11),
+                (3, 12),
+                (2, 12),
+                (2, 13),
+                (4, 13),
+            ],
+            &[(10, 2010), (11, 2000), (12, 2001), (13, 2005)],
+        );
+        let options = SearchOptions::default();
+        let path = chronological_path(
+            1,
+            4,
+            YearOrder::NonDecreasing,
+            &db,
+            &options,
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(actor_ids(&path), vec![1, 3, 2, 4]);
+        assert_eq!(path[2].movie_id, Some(12));
+
+        // the plain shortest path goes back in time
+        let path = shortest_path(1, 4, &db, &options, &SearchLimits::default())
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(actor_ids(&path), vec![1, 2, 4]);
+    }
+
+    #[tokio::test]
+    async fn chronological_strict() {
+        // two movies of the same year
+        let db = make_dated_db(
+            vec![(1, 10), (2, 10), (2, 11), (3, 11)],
+            &[(10, 2000), (11, 2000)],
+        );
+        let options = SearchOptions::default();
+        let path = chronological_path(
+            1,
+            3,
+            YearOrder::NonDecreasing,
+            &db,
+            &options,
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(actor_ids(&path), vec![1, 2, 3]);
+        let path = chronological_path(
+            1,
+            3,
+            YearOrder::Increasing,
+            &db,
+            &options,
+            &SearchLimits::default(),
+        )
+        .await;
+        assert_eq!(path, Err(SearchError::NoPath));
+    }
+
+    #[tokio::test]
+    async fn chronological_limits() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        let order = YearOrder::Increasing;
+        // the only chain has two hops
+        let limits = SearchLimits {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let path = chronological_path(129, 158, order, &db, &options, &limits).await;
+        assert_eq!(path, Err(SearchError::BudgetExceeded(Budget::Depth)));
+
+        let limits = SearchLimits {
+            max_nodes_explored: Some(1),
+            ..Default::default()
+        };
+        let path = chronological_path(129, 158, order, &db, &options, &limits).await;
+        assert_eq!(
+            path,
+            Err(SearchError::BudgetExceeded(Budget::NodesExplored))
+        );
+
+        let limits = SearchLimits::default();
+        limits.cancellation.cancel();
+        let path = chronological_path(129, 158, order, &db, &options, &limits).await;
+        assert_eq!(path, Err(SearchError::Cancelled));
+    }
+}
@@ -0,0 +1,190 @@
+use super::{ActorId, SearchError, SearchOptions};
+use crate::data::{ActorIndex, MovieDB};
+use std::collections::HashMap;
+
+/// How far every actor reachable from one actor is, in hops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceProfile {
+    pub source_actor_id: ActorId,
+    /// Hops from the source to every actor it can reach, the source included
+    /// at distance 0.
+    pub distances: HashMap<ActorId, u32>,
+    /// Number of actors at each distance, indexed by the distance.
+    pub histogram: Vec<usize>,
+}
+
+impl DistanceProfile {
+    /// Number of actors the source can reach, the source included.
+    pub fn num_reachable(&self) -> usize {
+        self.distances.len()
+    }
+
+    /// Largest distance from the source to an actor it can reach.
+    pub fn max_distance(&self) -> u32 {
+        (self.histogram.len() - 1) as u32
+    }
+
+    /// Mean distance from the source to the other actors it can reach, or
+    /// `None` if it reaches no one.
+    pub fn mean_distance(&self) -> Option<f64> {
+        let others = self.num_reachable() - 1;
+        if others == 0 {
+            return None;
+        }
+        let total: usize = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(distance, count)| distance * count)
+            .sum();
+        Some(total as f64 / others as f64)
+    }
+}
+
+/// Runs a BFS from one actor over the whole graph and returns the distance
+/// to every actor it reaches, the actor's "Bacon number" table.
+///
+/// Only goes through the actors and movies that `options` allows. An actor
+/// without credits only reaches themselves.
+pub async fn distance_profile(
+    source_actor_id: ActorId,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+) -> Result<DistanceProfile, SearchError> {
+    if !options.allows_actor(source_actor_id) {
+        return Err(SearchError::Excluded);
+    }
+    let connections = &movie_db.connections;
+    let Some(source) = connections.actor_index(source_actor_id) else {
+        return Ok(DistanceProfile {
+            source_actor_id,
+            distances: HashMap::from([(source_actor_id, 0)]),
+            histogram: vec![1],
+        });
+    };
+
+    // distances by actor index, u32::MAX for actors not reached yet
+    let mut distance_of = vec![u32::MAX; connections.num_actors()];
+    distance_of[source as usize] = 0;
+    let mut histogram = vec![1];
+    let mut frontier: Vec<ActorIndex> = vec![source];
+    let mut num_explored: usize = 0;
+
+    while !frontier.is_empty() {
+        let next_distance = histogram.len() as u32;
+        let mut next_frontier = Vec::new();
+        for actor in frontier {
+            // yield control to event loop
+            if num_explored.is_multiple_of(1000) {
+                tokio::task::yield_now().await;
+            }
+            num_explored += 1;
+
+            for &movie in connections.movies_of(actor) {
+                if !options.allows_movie(movie, movie_db) {
+                    continue;
+                }
+                for &co_star in connections.cast_of(movie) {
+                    if distance_of[co_star as usize] != u32::MAX
+                        || !options.allows_actor(connections.actor_id(co_star))
+                    {
+                        continue;
+                    }
+                    distance_of[co_star as usize] = next_distance;
+                    next_frontier.push(co_star);
+                }
+            }
+        }
+        if !next_frontier.is_empty() {
+            histogram.push(next_frontier.len());
+        }
+        frontier = next_frontier;
+    }
+
+    let distances = distance_of
+        .iter()
+        .enumerate()
+        .filter(|(_, distance)| **distance != u32::MAX)
+        .map(|(actor, distance)| (connections.actor_id(actor as ActorIndex), *distance))
+        .collect();
+    Ok(DistanceProfile {
+        source_actor_id,
+        distances,
+        histogram,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::test::make_test_db;
+    use crate::graph::{SearchLimits, shortest_path};
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn distance_profile_bacon() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        let profile = distance_profile(102, &db, &options).await.unwrap();
+
+        // Kevin Bacon's co-stars in A Few Good Men and Apollo 13, then Tom
+        // Cruise's in Rain Man and Tom Hanks' in Forrest Gump, then the cast
+        // of The Princess Bride
+        assert_eq!(profile.histogram, vec![1, 6, 5, 3]);
+        assert_eq!(profile.num_reachable(), 15);
+        assert_eq!(profile.max_distance(), 3);
+        assert_eq!(profile.distances[&102], 0);
+        assert_eq!(profile.distances[&129], 1);
+        assert_eq!(profile.distances[&705], 2);
+        assert_eq!(profile.distances[&144], 3);
+
+        // every distance is the length of a shortest path
+        for (&actor_id, &distance) in &profile.distances {
+            let path = shortest_path(102, actor_id, &db, &options, &SearchLimits::default())
+                .await
+                .unwrap()
+                .0;
+            assert_eq!(path.len() - 1, distance as usize);
+        }
+    }
+
+    #[tokio::test]
+    async fn distance_profile_mean() {
+        let db = make_test_db();
+        let profile = distance_profile(102, &db, &SearchOptions::default())
+            .await
+            .unwrap();
+        let mean = (6 + 5 * 2 + 3 * 3) as f64 / 14.0;
+        assert_eq!(profile.mean_distance(), Some(mean));
+    }
+
+    #[tokio::test]
+    async fn distance_profile_with_options() {
+        let db = make_test_db();
+        let options = SearchOptions {
+            excluded_movies: HashSet::from([112384]), // Apollo 13
+            ..Default::default()
+        };
+        // Kevin Bacon is cut off from Tom Hanks' side of the graph
+        let profile = distance_profile(102, &db, &options).await.unwrap();
+        assert_eq!(profile.histogram, vec![1, 3, 3]);
+        assert!(!profile.distances.contains_key(&158));
+
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([102]),
+            ..Default::default()
+        };
+        let profile = distance_profile(102, &db, &options).await;
+        assert_eq!(profile, Err(SearchError::Excluded));
+    }
+
+    #[tokio::test]
+    async fn distance_profile_no_credits() {
+        let db = make_test_db();
+        let profile = distance_profile(1, &db, &SearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(profile.histogram, vec![1]);
+        assert_eq!(profile.mean_distance(), None);
+    }
+}
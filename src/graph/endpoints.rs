@@ -0,0 +1,195 @@
+use super::{
+    ActorId, MovieId, Node, SearchError, SearchLimits, SearchOptions, bidirectional_search,
+};
+use crate::data::MovieDB;
+
+/// One end of a chain of co-stars: an actor, or a movie the chain starts or
+/// ends with one of the cast of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    Actor(ActorId),
+    Movie(MovieId),
+}
+
+impl Endpoint {
+    fn movie_id(&self) -> Option<MovieId> {
+        match *self {
+            Endpoint::Actor(_) => None,
+            Endpoint::Movie(movie_id) => Some(movie_id),
+        }
+    }
+
+    /// The actors a search from the endpoint grows from. A movie the
+    /// options hide has none.
+    fn actor_ids(&self, movie_db: &MovieDB, options: &SearchOptions) -> Vec<ActorId> {
+        match *self {
+            Endpoint::Actor(actor_id) => vec![actor_id],
+            Endpoint::Movie(movie_id) => match movie_db.connections.movie_index(movie_id) {
+                Some(movie) if options.allows_movie(movie, movie_db) => {
+                    movie_db.actors_of_movie(movie_id).collect()
+                }
+                _ => Vec::new(),
+            },
+        }
+    }
+}
+
+/// A chain of co-stars between two endpoints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointPath {
+    /// The movie the chain starts from, which its first actor was in.
+    pub source_movie: Option<MovieId>,
+    /// The actors of the chain, as [`shortest_path`](super::shortest_path)
+    /// returns them. Empty when both ends are the same movie.
+    pub path: Vec<Node>,
+    /// The movie the chain ends at, which its last actor was in.
+    pub target_movie: Option<MovieId>,
+}
+
+impl EndpointPath {
+    /// Steps along the chain, each from an actor to a co-star or between a
+    /// movie at either end and the actor of its cast the chain goes through.
+    pub fn num_hops(&self) -> usize {
+        if self.path.is_empty() {
+            return 0;
+        }
+        self.path.len() - 1
+            + usize::from(self.source_movie.is_some())
+            + usize::from(self.target_movie.is_some())
+    }
+}
+
+/// Finds a shortest chain of co-stars between two endpoints, either of
+/// which may be a movie.
+///
+/// A movie stands for its whole cast, so the search grows from all of them
+/// at once, as the backward search of [`nearest_actor`](super::nearest_actor)
+/// does from all its targets, and the chain has as few hops as it can. Only
+/// goes through the actors and movies that `options` allows, which leaves no
+/// chain from or to a hidden movie.
+pub async fn endpoint_path(
+    source: Endpoint,
+    target: Endpoint,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+) -> Result<EndpointPath, SearchError> {
+    let sources = source.actor_ids(movie_db, options);
+    let targets = target.actor_ids(movie_db, options);
+    if let Endpoint::Movie(_) = source
+        && source == target
+    {
+        return if sources.is_empty() {
+            Err(SearchError::NoPath)
+        } else {
+            Ok(EndpointPath {
+                source_movie: source.movie_id(),
+                path: Vec::new(),
+                target_movie: target.movie_id(),
+            })
+        };
+    }
+    let path = bidirectional_search(&sources, &targets, movie_db, options, limits, |_, _, _| {
+        true
+    })
+    .await
+    .0?;
+    Ok(EndpointPath {
+        source_movie: source.movie_id(),
+        path,
+        target_movie: target.movie_id(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::test::{actor_ids, make_test_db};
+    use std::collections::HashSet;
+
+    async fn find(source: Endpoint, target: Endpoint, options: &SearchOptions) -> EndpointPath {
+        let db = make_test_db();
+        endpoint_path(source, target, &db, options, &SearchLimits::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn movie_to_movie() {
+        let options = SearchOptions::default();
+        // Robin Wright was in The Princess Bride and Forrest Gump, with two
+        // actors of Apollo 13
+        let found = find(Endpoint::Movie(93779), Endpoint::Movie(112384), &options).await;
+        assert_eq!(found.source_movie, Some(93779));
+        assert_eq!(found.target_movie, Some(112384));
+        assert_eq!(found.path.len(), 2);
+        assert_eq!(found.path[0].actor_id, 705);
+        assert_eq!(found.path[1].movie_id, Some(109830));
+        assert!([158, 641].contains(&found.path[1].actor_id));
+        assert_eq!(found.num_hops(), 3);
+
+        // Kevin Bacon was in both
+        let found = find(Endpoint::Movie(104257), Endpoint::Movie(112384), &options).await;
+        assert_eq!(actor_ids(&found.path), vec![102]);
+        assert_eq!(found.num_hops(), 2);
+
+        let found = find(Endpoint::Movie(93779), Endpoint::Movie(93779), &options).await;
+        assert!(found.path.is_empty());
+        assert_eq!(found.num_hops(), 0);
+    }
+
+    #[tokio::test]
+    async fn actor_to_movie() {
+        let options = SearchOptions::default();
+        let found = find(Endpoint::Actor(102), Endpoint::Movie(104257), &options).await;
+        assert_eq!(actor_ids(&found.path), vec![102]);
+        assert_eq!(found.num_hops(), 1);
+
+        // Kevin Bacon, a Forrest Gump actor of Apollo 13, then Robin Wright
+        let found = find(Endpoint::Actor(102), Endpoint::Movie(93779), &options).await;
+        assert_eq!(found.source_movie, None);
+        assert_eq!(found.path.len(), 3);
+        assert_eq!(found.path[2].actor_id, 705);
+        assert_eq!(found.num_hops(), 3);
+
+        // and the other way round
+        let found = find(Endpoint::Movie(93779), Endpoint::Actor(102), &options).await;
+        assert_eq!(found.path[0].actor_id, 705);
+        assert_eq!(found.path.last().unwrap().actor_id, 102);
+        assert_eq!(found.num_hops(), 3);
+    }
+
+    #[tokio::test]
+    async fn endpoint_path_options() {
+        let db = make_test_db();
+        let limits = SearchLimits::default();
+        let options = SearchOptions {
+            excluded_movies: HashSet::from([93779]),
+            ..Default::default()
+        };
+        let found = endpoint_path(
+            Endpoint::Actor(102),
+            Endpoint::Movie(93779),
+            &db,
+            &options,
+            &limits,
+        )
+        .await;
+        assert_eq!(found, Err(SearchError::NoPath));
+
+        // without Robin Wright nothing links The Princess Bride to the rest
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([705]),
+            ..Default::default()
+        };
+        let found = endpoint_path(
+            Endpoint::Movie(93779),
+            Endpoint::Movie(112384),
+            &db,
+            &options,
+            &limits,
+        )
+        .await;
+        assert_eq!(found, Err(SearchError::NoPath));
+    }
+}
@@ -0,0 +1,124 @@
+//! Binary layout shared by the search indices saved next to the dataset:
+//! a magic string with the format version, the actor id of every actor
+//! index and a fingerprint of the credits so a changed dataset is caught on
+//! load, then the index itself.
+use crate::data::{ActorIndex, Connections};
+use std::io::{Error as IoError, ErrorKind, Write};
+
+pub(super) fn invalid(message: &str) -> IoError {
+    IoError::new(ErrorKind::InvalidData, message.to_string())
+}
+
+/// FNV-1a parameters. Unlike the std hashers, FNV gives the same hash on
+/// every build, which a file read by later builds needs.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Hash of every credit of `connections`: the movies of each actor index,
+/// by movie id. Indices built before a credit was added or removed have
+/// another fingerprint, even when the actors are the same.
+fn fingerprint(connections: &Connections) -> u64 {
+    let mut hash = FNV_OFFSET;
+    let mut add = |value: u64| {
+        for byte in value.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+    add(connections.num_movies() as u64);
+    for actor in 0..connections.num_actors() as ActorIndex {
+        let movies = connections.movies_of(actor);
+        add(movies.len() as u64);
+        for &movie in movies {
+            add(connections.movie_id(movie) as u64);
+        }
+    }
+    hash
+}
+
+pub(super) fn write_u64<W: Write>(writer: &mut W, value: u64) -> Result<(), IoError> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+/// Writes the magic string, the actor ids of `connections`, and the number
+/// and fingerprint of their credits.
+pub(super) fn write_header<W: Write>(
+    writer: &mut W,
+    magic: &[u8; 8],
+    connections: &Connections,
+) -> Result<(), IoError> {
+    writer.write_all(magic)?;
+    write_u64(writer, connections.num_actors() as u64)?;
+    for actor in 0..connections.num_actors() as ActorIndex {
+        write_u64(writer, connections.actor_id(actor) as u64)?;
+    }
+    write_u64(writer, connections.num_credits() as u64)?;
+    write_u64(writer, fingerprint(connections))
+}
+
+/// Reads an index file from memory, front to back.
+pub(super) struct IndexReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> IndexReader<'a> {
+    /// Checks the magic string and that the actor ids and credits are those
+    /// of `connections`.
+    pub(super) fn open(
+        bytes: &'a [u8],
+        magic: &[u8; 8],
+        connections: &Connections,
+    ) -> Result<IndexReader<'a>, IoError> {
+        let bytes = bytes
+            .strip_prefix(magic)
+            .ok_or_else(|| invalid("not an index file of this kind"))?;
+        let mut reader = IndexReader { bytes };
+        if reader.read_u64()? != connections.num_actors() as u64 {
+            return Err(invalid("index was built from another dataset"));
+        }
+        for actor in 0..connections.num_actors() as ActorIndex {
+            if reader.read_u64()? != connections.actor_id(actor) as u64 {
+                return Err(invalid("index was built from another dataset"));
+            }
+        }
+        if reader.read_u64()? != connections.num_credits() as u64
+            || reader.read_u64()? != fingerprint(connections)
+        {
+            return Err(invalid("index was built from other credits"));
+        }
+        Ok(reader)
+    }
+
+    pub(super) fn read_u64(&mut self) -> Result<u64, IoError> {
+        let (value, rest) = self
+            .bytes
+            .split_first_chunk::<8>()
+            .ok_or_else(|| invalid("index file is truncated"))?;
+        self.bytes = rest;
+        Ok(u64::from_le_bytes(*value))
+    }
+
+    /// Number of bytes left to read.
+    pub(super) fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// The next `len` bytes.
+    pub(super) fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], IoError> {
+        if self.bytes.len() < len {
+            return Err(invalid("index file is truncated"));
+        }
+        let (value, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(value)
+    }
+
+    /// Fails if anything is left after the index.
+    pub(super) fn finish(self) -> Result<(), IoError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid("index file is too long"))
+        }
+    }
+}
@@ -0,0 +1,397 @@
+use super::index_file::{IndexReader, invalid, write_header, write_u64};
+use super::weighted::chain_to;
+use super::{ActorId, Node, SearchError, SearchOptions};
+use crate::data::{ActorIndex, Connections, MovieDB};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs;
+use std::io::{BufWriter, Error as IoError, Write};
+use std::path::Path;
+
+/// Name of the file [`Landmarks::save`] writes next to the dataset.
+pub const LANDMARKS_FILE: &str = "landmarks.bin";
+
+/// Marks the start of a landmarks file, with its format version.
+const MAGIC: &[u8; 8] = b"CSLMRK03";
+
+/// Distance of actors a landmark doesn't reach.
+const UNREACHED: u16 = u16::MAX;
+
+/// Hop distances from a few landmark actors to every actor, for
+/// [`landmark_path`] to bound how far apart two actors are.
+///
+/// By the triangle inequality two actors are at least `|d(L, a) - d(L, b)|`
+/// hops apart for any landmark `L`. Landmarks spread out on the edge of the
+/// graph give the tightest bounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Landmarks {
+    landmark_ids: Vec<ActorId>,
+    /// Distance from every landmark to every actor, the landmarks of one
+    /// actor next to each other: `distances[actor * num_landmarks + landmark]`.
+    distances: Vec<u16>,
+}
+
+impl Landmarks {
+    /// Picks up to `count` landmarks in the largest component and runs a BFS
+    /// from each.
+    ///
+    /// The first landmark is the actor with the most credits, and every next
+    /// one the actor farthest from the landmarks picked so far. Stops early
+    /// when every actor of the component is a landmark.
+    pub fn select(movie_db: &MovieDB, count: usize) -> Landmarks {
+        let connections = &movie_db.connections;
+        let num_actors = connections.num_actors();
+        let components = connections.components();
+        let first = (0..num_actors as ActorIndex)
+            .filter(|&actor| components.component_of(actor) == 0)
+            .max_by_key(|&actor| (connections.movies_of(actor).len(), Reverse(actor)));
+
+        let mut landmarks = Vec::new();
+        let mut runs: Vec<Vec<u16>> = Vec::new();
+        // distance from every actor to the nearest landmark
+        let mut nearest = vec![UNREACHED; num_actors];
+        let mut next = first;
+        while let Some(landmark) = next
+            && landmarks.len() < count
+        {
+            let run = bfs(connections, landmark);
+            for (nearest, &distance) in nearest.iter_mut().zip(&run) {
+                *nearest = (*nearest).min(distance);
+            }
+            landmarks.push(landmark);
+            runs.push(run);
+            next = (0..num_actors as ActorIndex)
+                .filter(|&actor| nearest[actor as usize] != UNREACHED)
+                .filter(|&actor| nearest[actor as usize] > 0)
+                .max_by_key(|&actor| (nearest[actor as usize], Reverse(actor)));
+        }
+
+        let mut distances = Vec::with_capacity(num_actors * runs.len());
+        for actor in 0..num_actors {
+            distances.extend(runs.iter().map(|run| run[actor]));
+        }
+        Landmarks {
+            landmark_ids: landmarks
+                .into_iter()
+                .map(|landmark| connections.actor_id(landmark))
+                .collect(),
+            distances,
+        }
+    }
+
+    pub fn landmark_ids(&self) -> &[ActorId] {
+        &self.landmark_ids
+    }
+
+    /// Fewest hops there can be between the two actors.
+    fn lower_bound(&self, actor: ActorIndex, other: ActorIndex) -> u32 {
+        let num_landmarks = self.landmark_ids.len();
+        let row = |actor: ActorIndex| {
+            let start = actor as usize * num_landmarks;
+            &self.distances[start..start + num_landmarks]
+        };
+        row(actor)
+            .iter()
+            .zip(row(other))
+            .filter(|&(&a, &b)| a != UNREACHED && b != UNREACHED)
+            .map(|(a, b)| a.abs_diff(*b) as u32)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Heap bytes used by the distances.
+    pub fn heap_size(&self) -> usize {
+        self.distances.capacity() * size_of::<u16>()
+            + self.landmark_ids.capacity() * size_of::<ActorId>()
+    }
+
+    /// Writes the landmarks to `path`, with the actor ids of `connections`
+    /// so that [`Landmarks::load`] can tell whether the dataset changed.
+    pub fn save(&self, path: &Path, connections: &Connections) -> Result<(), IoError> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        write_header(&mut writer, MAGIC, connections)?;
+        write_u64(&mut writer, self.landmark_ids.len() as u64)?;
+        for &landmark_id in &self.landmark_ids {
+            write_u64(&mut writer, landmark_id as u64)?;
+        }
+        for &distance in &self.distances {
+            writer.write_all(&distance.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Reads landmarks written by [`Landmarks::save`], failing if they were
+    /// worked out on other connections than `connections`.
+    pub fn load(path: &Path, connections: &Connections) -> Result<Landmarks, IoError> {
+        let bytes = fs::read(path)?;
+        let mut reader = IndexReader::open(&bytes, MAGIC, connections)?;
+        // every landmark takes at least its 8 byte id, so a count the rest of
+        // the file can't hold is corrupt
+        let num_landmarks = reader.read_u64()?;
+        if num_landmarks > (reader.remaining() / size_of::<u64>()) as u64 {
+            return Err(invalid("index file is truncated"));
+        }
+        let num_landmarks = num_landmarks as usize;
+        let len = num_landmarks
+            .checked_mul(connections.num_actors())
+            .and_then(|len| len.checked_mul(size_of::<u16>()))
+            .ok_or_else(|| invalid("index file is truncated"))?;
+        let mut landmark_ids = Vec::with_capacity(num_landmarks);
+        for _ in 0..num_landmarks {
+            landmark_ids.push(reader.read_u64()? as ActorId);
+        }
+        let distances = reader
+            .read_bytes(len)?
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect();
+        reader.finish()?;
+        Ok(Landmarks {
+            landmark_ids,
+            distances,
+        })
+    }
+}
+
+/// Hops from `source` to every actor, [`UNREACHED`] for the actors in other
+/// components.
+fn bfs(connections: &Connections, source: ActorIndex) -> Vec<u16> {
+    let mut distances = vec![UNREACHED; connections.num_actors()];
+    distances[source as usize] = 0;
+    let mut order = vec![source];
+    let mut next = 0;
+    while next < order.len() {
+        let actor = order[next];
+        next += 1;
+        let distance = distances[actor as usize] + 1;
+        for &movie in connections.movies_of(actor) {
+            for &co_star in connections.cast_of(movie) {
+                if distances[co_star as usize] == UNREACHED {
+                    distances[co_star as usize] = distance;
+                    order.push(co_star);
+                }
+            }
+        }
+    }
+    distances
+}
+
+/// Finds a shortest chain of co-stars between two actors with an A* search
+/// steered by the landmark bounds (ALT).
+///
+/// Returns a path as long as [`super::shortest_path`]'s, though it may pick
+/// another one when there are several, and only goes through the actors and
+/// movies that `options` allows. Excluding actors or movies only makes
+/// chains longer, so the bounds still hold. `landmarks` must have been
+/// worked out on `movie_db`.
+pub async fn landmark_path(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    landmarks: &Landmarks,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+) -> Result<Vec<Node>, SearchError> {
+    if !options.allows_actor(source_actor_id) || !options.allows_actor(target_actor_id) {
+        return Err(SearchError::NoPath);
+    }
+    if source_actor_id == target_actor_id {
+        return Ok(vec![Node::new(source_actor_id, None, None)]);
+    }
+    if !movie_db.connected(source_actor_id, target_actor_id) {
+        return Err(SearchError::NoPath);
+    }
+    let connections = &movie_db.connections;
+    let (Some(source), Some(target)) = (
+        connections.actor_index(source_actor_id),
+        connections.actor_index(target_actor_id),
+    ) else {
+        // one of the actors has no credits
+        return Err(SearchError::NoPath);
+    };
+
+    let mut distances: HashMap<ActorIndex, u32> = HashMap::from([(source, 0)]);
+    let mut parents = HashMap::new();
+    // ordered by estimated length, then by the bound so that ties go to the
+    // actors nearer the target
+    let bound = landmarks.lower_bound(source, target);
+    let mut queue = BinaryHeap::from([Reverse((bound, bound, source))]);
+    let mut num_explored: usize = 0;
+
+    while let Some(Reverse((estimate, bound, actor))) = queue.pop() {
+        let distance = estimate - bound;
+        if distance > distances[&actor] {
+            // stale entry, the actor was reached through a shorter chain
+            continue;
+        }
+        if actor == target {
+            return Ok(chain_to(target, &parents, movie_db));
+        }
+        // yield control to event loop
+        if num_explored.is_multiple_of(1000) {
+            tokio::task::yield_now().await;
+        }
+        num_explored += 1;
+
+        let next_distance = distance + 1;
+        for &movie in connections.movies_of(actor) {
+            if !options.allows_movie(movie, movie_db) {
+                continue;
+            }
+            for &co_star in connections.cast_of(movie) {
+                if !options.allows_actor(connections.actor_id(co_star)) {
+                    continue;
+                }
+                match distances.entry(co_star) {
+                    Entry::Occupied(entry) if *entry.get() <= next_distance => continue,
+                    Entry::Occupied(mut entry) => {
+                        entry.insert(next_distance);
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(next_distance);
+                    }
+                }
+                parents.insert(co_star, (actor, movie));
+                let bound = landmarks.lower_bound(co_star, target);
+                queue.push(Reverse((next_distance + bound, bound, co_star)));
+            }
+        }
+    }
+    Err(SearchError::NoPath)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::MovieDBBuilder;
+    use crate::graph::test::{all_actor_ids, assert_chain, make_test_db};
+    use crate::graph::{SearchLimits, distance_profile, shortest_path};
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    #[test]
+    fn landmarks_select() {
+        let db = make_test_db();
+        let landmarks = Landmarks::select(&db, 4);
+        let ids = landmarks.landmark_ids();
+        assert_eq!(ids.len(), 4);
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 4);
+        // there are only 15 actors to pick from
+        assert_eq!(Landmarks::select(&db, 100).landmark_ids().len(), 15);
+    }
+
+    #[tokio::test]
+    async fn landmarks_lower_bounds() {
+        let db = make_test_db();
+        let landmarks = Landmarks::select(&db, 3);
+        let connections = &db.connections;
+        let options = SearchOptions::default();
+        for source_id in all_actor_ids(&db) {
+            let profile = distance_profile(source_id, &db, &options).await.unwrap();
+            let source = connections.actor_index(source_id).unwrap();
+            for (&target_id, &distance) in &profile.distances {
+                let target = connections.actor_index(target_id).unwrap();
+                assert!(landmarks.lower_bound(source, target) <= distance);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn landmark_path_matches_shortest_path() {
+        let db = make_test_db();
+        let landmarks = Landmarks::select(&db, 3);
+        let options = SearchOptions::default();
+        for source in all_actor_ids(&db) {
+            for target in all_actor_ids(&db) {
+                let path = landmark_path(source, target, &landmarks, &db, &options)
+                    .await
+                    .unwrap();
+                let shortest =
+                    shortest_path(source, target, &db, &options, &SearchLimits::default())
+                        .await
+                        .unwrap()
+                        .0;
+                assert_eq!(path.len(), shortest.len());
+                assert_eq!(path.first().unwrap().actor_id, source);
+                assert_eq!(path.last().unwrap().actor_id, target);
+                assert_chain(&path, &db);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn landmark_path_with_options() {
+        let db = make_test_db();
+        let landmarks = Landmarks::select(&db, 2);
+        let options = SearchOptions {
+            excluded_movies: HashSet::from([104257]), // A Few Good Men
+            ..Default::default()
+        };
+        for target in all_actor_ids(&db) {
+            let path = landmark_path(102, target, &landmarks, &db, &options).await;
+            let shortest =
+                shortest_path(102, target, &db, &options, &SearchLimits::default()).await;
+            assert_eq!(
+                path.ok().map(|path| path.len()),
+                shortest.ok().map(|(path, _)| path.len())
+            );
+        }
+
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([102]), // Kevin Bacon
+            ..Default::default()
+        };
+        let path = landmark_path(129, 158, &landmarks, &db, &options).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+    }
+
+    #[test]
+    fn landmarks_save_and_load() {
+        let db = make_test_db();
+        let landmarks = Landmarks::select(&db, 3);
+        let path = std::env::temp_dir().join(format!("landmarks_{}.bin", std::process::id()));
+        landmarks.save(&path, &db.connections).unwrap();
+        let loaded = Landmarks::load(&path, &db.connections).unwrap();
+        assert_eq!(loaded, landmarks);
+
+        // the same number of actors under other ids
+        let other = MovieDBBuilder::get_connections(
+            all_actor_ids(&db)
+                .into_iter()
+                .map(|actor_id| (actor_id + 1, 1))
+                .collect(),
+        );
+        let err = Landmarks::load(&path, &other).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // the same actors and number of credits, but Tom Cruise was in
+        // Apollo 13 rather than A Few Good Men, so the bounds are off
+        let data_file = PathBuf::from("data/new_small/connections.csv");
+        let pairs: Vec<(usize, usize)> = MovieDBBuilder::read_actor_movie_pairs(&data_file)
+            .unwrap()
+            .into_iter()
+            .map(|pair| {
+                if pair == (129, 104257) {
+                    (129, 112384)
+                } else {
+                    pair
+                }
+            })
+            .collect();
+        let stale = MovieDBBuilder::get_connections(pairs);
+        assert_eq!(stale.num_credits(), db.connections.num_credits());
+        let err = Landmarks::load(&path, &stale).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // a corrupt landmark count is caught before anything is allocated
+        let mut bytes = fs::read(&path).unwrap();
+        let count_start = 8 + 8 * (db.connections.num_actors() + 3);
+        for count in [4, u64::MAX / 2, u64::MAX] {
+            bytes[count_start..count_start + 8].copy_from_slice(&count.to_le_bytes());
+            fs::write(&path, &bytes).unwrap();
+            let err = Landmarks::load(&path, &db.connections).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,404 @@
+use super::index_file::{IndexReader, invalid, write_header, write_u64};
+use super::{ActorId, Node, index_path};
+use crate::data::{ActorIndex, Connections, MovieDB};
+use std::cmp::Reverse;
+use std::fs;
+use std::io::{BufWriter, Error as IoError, Write};
+use std::path::Path;
+
+/// Name of the file [`DistanceOracle::save`] writes next to the dataset.
+pub const ORACLE_FILE: &str = "distance_oracle.bin";
+
+/// Marks the start of an oracle file, with its format version.
+const MAGIC: &[u8; 8] = b"CSPLL002";
+
+/// Distance of actors not reached yet while building.
+const UNREACHED: u16 = u16::MAX;
+
+/// Exact hop distances between any two actors, answered from precomputed
+/// labels without searching the graph (pruned landmark labeling).
+///
+/// Every actor gets a label: a few hub actors with the distance to each.
+/// Labels form a 2-hop cover, so some shortest path between any two actors
+/// goes through a hub they share and their distance is the smallest sum
+/// over the shared hubs. Hubs are the actors with the most co-stars, which
+/// lie on most shortest paths and keep the labels short.
+///
+/// The labels cover the whole graph, so the oracle can't answer searches
+/// that exclude actors or movies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceOracle {
+    /// Where the label of every actor index starts in `hubs`, and one past
+    /// the last label.
+    offsets: Vec<usize>,
+    /// Hubs of every label by rank, the actor with the most co-stars being
+    /// rank 0, in increasing rank order.
+    hubs: Vec<u32>,
+    /// Hops to the hub at the same position in `hubs`.
+    hub_distances: Vec<u16>,
+}
+
+impl DistanceOracle {
+    /// Builds the labels with one pruned BFS per actor, from the actors with
+    /// the most co-stars down. A BFS from a hub stops at the actors whose
+    /// distance to it the labels found so far already give, so later ones
+    /// only visit a small neighbourhood.
+    pub fn build(movie_db: &MovieDB) -> DistanceOracle {
+        let connections = &movie_db.connections;
+        let num_actors = connections.num_actors();
+        let mut order: Vec<ActorIndex> = (0..num_actors as ActorIndex).collect();
+        // co-stars counted once per shared movie, which is close enough
+        order.sort_by_key(|&actor| {
+            let co_stars: usize = connections
+                .movies_of(actor)
+                .iter()
+                .map(|&movie| connections.cast_of(movie).len())
+                .sum();
+            (Reverse(co_stars), actor)
+        });
+
+        let mut labels: Vec<Vec<(u32, u16)>> = vec![Vec::new(); num_actors];
+        // distance from the hub of the current BFS to each of its own hubs
+        let mut hub_label = vec![UNREACHED; num_actors];
+        let mut distances = vec![UNREACHED; num_actors];
+        let mut visited: Vec<ActorIndex> = Vec::new();
+        for (rank, &hub) in order.iter().enumerate() {
+            let rank = rank as u32;
+            for &(other_hub, distance) in &labels[hub as usize] {
+                hub_label[other_hub as usize] = distance;
+            }
+
+            distances[hub as usize] = 0;
+            visited.push(hub);
+            let mut next = 0;
+            while next < visited.len() {
+                let actor = visited[next];
+                next += 1;
+                let distance = distances[actor as usize];
+                // skip the actors the labels already give a path to
+                let covered = labels[actor as usize].iter().any(|&(other_hub, other)| {
+                    let to_hub = hub_label[other_hub as usize];
+                    to_hub != UNREACHED && to_hub as u32 + other as u32 <= distance as u32
+                });
+                if covered {
+                    continue;
+                }
+                labels[actor as usize].push((rank, distance));
+                for &movie in connections.movies_of(actor) {
+                    for &co_star in connections.cast_of(movie) {
+                        if distances[co_star as usize] == UNREACHED {
+                            distances[co_star as usize] = distance + 1;
+                            visited.push(co_star);
+                        }
+                    }
+                }
+            }
+
+            for actor in visited.drain(..) {
+                distances[actor as usize] = UNREACHED;
+            }
+            for &(other_hub, _) in &labels[hub as usize] {
+                hub_label[other_hub as usize] = UNREACHED;
+            }
+        }
+
+        let mut offsets = Vec::with_capacity(num_actors + 1);
+        let mut hubs = Vec::new();
+        let mut hub_distances = Vec::new();
+        offsets.push(0);
+        for label in labels {
+            hubs.extend(label.iter().map(|&(hub, _)| hub));
+            hub_distances.extend(label.iter().map(|&(_, distance)| distance));
+            offsets.push(hubs.len());
+        }
+        DistanceOracle {
+            offsets,
+            hubs,
+            hub_distances,
+        }
+    }
+
+    /// Number of hops between two actors, `None` when no chain of co-stars
+    /// links them.
+    pub fn distance(
+        &self,
+        actor_id_1: ActorId,
+        actor_id_2: ActorId,
+        connections: &Connections,
+    ) -> Option<u32> {
+        if actor_id_1 == actor_id_2 {
+            return Some(0);
+        }
+        let actor_1 = connections.actor_index(actor_id_1)?;
+        let actor_2 = connections.actor_index(actor_id_2)?;
+        self.index_distance(actor_1, actor_2)
+    }
+
+    /// Merges the two sorted labels, looking for the nearest shared hub.
+    fn index_distance(&self, actor_1: ActorIndex, actor_2: ActorIndex) -> Option<u32> {
+        let label = |actor: ActorIndex| {
+            let range = self.offsets[actor as usize]..self.offsets[actor as usize + 1];
+            (&self.hubs[range.clone()], &self.hub_distances[range])
+        };
+        let (hubs_1, distances_1) = label(actor_1);
+        let (hubs_2, distances_2) = label(actor_2);
+        let (mut i, mut j) = (0, 0);
+        let mut best = None;
+        while i < hubs_1.len() && j < hubs_2.len() {
+            match hubs_1[i].cmp(&hubs_2[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    let distance = distances_1[i] as u32 + distances_2[j] as u32;
+                    best = Some(best.map_or(distance, |best: u32| best.min(distance)));
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        best
+    }
+
+    /// A shortest chain of co-stars between two actors, in the same form as
+    /// [`super::shortest_path`]'s, or `None` when none links them or the
+    /// labels don't fit the connections.
+    ///
+    /// Walks from the source to a co-star one hop nearer the target at each
+    /// step, so it only asks the oracle about the co-stars along the way.
+    pub fn path(
+        &self,
+        source_actor_id: ActorId,
+        target_actor_id: ActorId,
+        movie_db: &MovieDB,
+    ) -> Option<Vec<Node>> {
+        let connections = &movie_db.connections;
+        let mut path = vec![Node::new(source_actor_id, None, None)];
+        if source_actor_id == target_actor_id {
+            return Some(path);
+        }
+        let mut actor = connections.actor_index(source_actor_id)?;
+        let target = connections.actor_index(target_actor_id)?;
+        let mut distance = self.index_distance(actor, target)?;
+        while distance > 0 {
+            let (movie, co_star) = connections
+                .movies_of(actor)
+                .iter()
+                .flat_map(|&movie| {
+                    connections
+                        .cast_of(movie)
+                        .iter()
+                        .map(move |&co_star| (movie, co_star))
+                })
+                .find(|&(_, co_star)| self.index_distance(co_star, target) == Some(distance - 1))?;
+            path.push(Node::new(
+                connections.actor_id(co_star),
+                None,
+                Some(connections.movie_id(movie)),
+            ));
+            actor = co_star;
+            distance -= 1;
+        }
+        index_path(&mut path);
+        Some(path)
+    }
+
+    /// Number of hubs in all the labels together.
+    pub fn num_entries(&self) -> usize {
+        self.hubs.len()
+    }
+
+    /// Heap bytes used by the labels.
+    pub fn heap_size(&self) -> usize {
+        self.offsets.capacity() * size_of::<usize>()
+            + self.hubs.capacity() * size_of::<u32>()
+            + self.hub_distances.capacity() * size_of::<u16>()
+    }
+
+    /// Writes the labels to `path`, with the actor ids of `connections` so
+    /// that [`DistanceOracle::load`] can tell whether the dataset changed.
+    pub fn save(&self, path: &Path, connections: &Connections) -> Result<(), IoError> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        write_header(&mut writer, MAGIC, connections)?;
+        for &offset in &self.offsets {
+            write_u64(&mut writer, offset as u64)?;
+        }
+        for &hub in &self.hubs {
+            writer.write_all(&hub.to_le_bytes())?;
+        }
+        for &distance in &self.hub_distances {
+            writer.write_all(&distance.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Reads labels written by [`DistanceOracle::save`], failing if they
+    /// were built from other connections than `connections`.
+    pub fn load(path: &Path, connections: &Connections) -> Result<DistanceOracle, IoError> {
+        let bytes = fs::read(path)?;
+        let mut reader = IndexReader::open(&bytes, MAGIC, connections)?;
+        let num_actors = connections.num_actors();
+        // every label takes at least 6 bytes, so the offsets can't point past
+        // what is left of the file
+        let max_entries = reader.remaining() / (size_of::<u32>() + size_of::<u16>());
+        let mut offsets = Vec::with_capacity(num_actors + 1);
+        for _ in 0..=num_actors {
+            let offset = reader.read_u64()?;
+            let previous = offsets.last().copied().unwrap_or(0);
+            if offsets.is_empty() && offset != 0 {
+                return Err(invalid("the first label doesn't start at 0"));
+            }
+            if offset < previous as u64 || offset > max_entries as u64 {
+                return Err(invalid("label offsets are out of order or out of range"));
+            }
+            offsets.push(offset as usize);
+        }
+        let num_entries = *offsets.last().unwrap();
+        let hubs: Vec<u32> = reader
+            .read_bytes(num_entries * size_of::<u32>())?
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        if hubs.iter().any(|&hub| hub as usize >= num_actors) {
+            return Err(invalid("a hub is not an actor"));
+        }
+        let hub_distances = reader
+            .read_bytes(num_entries * size_of::<u16>())?
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect();
+        reader.finish()?;
+        Ok(DistanceOracle {
+            offsets,
+            hubs,
+            hub_distances,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::MovieDBBuilder;
+    use crate::graph::test::{all_actor_ids, assert_chain, make_db, make_test_db};
+    use crate::graph::{SearchLimits, SearchOptions, distance_profile, shortest_path};
+    use std::collections::HashMap;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn oracle_distances_match_bfs() {
+        let db = make_test_db();
+        let oracle = DistanceOracle::build(&db);
+        let options = SearchOptions::default();
+        for source in all_actor_ids(&db) {
+            let profile = distance_profile(source, &db, &options).await.unwrap();
+            for target in all_actor_ids(&db) {
+                let distance = oracle.distance(source, target, &db.connections);
+                assert_eq!(distance, profile.distances.get(&target).copied());
+            }
+        }
+        // every actor is their own hub at worst, but most share Kevin Bacon
+        assert!(oracle.num_entries() < 15 * 15);
+    }
+
+    #[tokio::test]
+    async fn oracle_path_matches_shortest_path() {
+        let db = make_test_db();
+        let oracle = DistanceOracle::build(&db);
+        let options = SearchOptions::default();
+        for source in all_actor_ids(&db) {
+            for target in all_actor_ids(&db) {
+                let path = oracle.path(source, target, &db).unwrap();
+                let shortest =
+                    shortest_path(source, target, &db, &options, &SearchLimits::default())
+                        .await
+                        .unwrap()
+                        .0;
+                assert_eq!(path.len(), shortest.len());
+                assert_eq!(path.first().unwrap().actor_id, source);
+                assert_eq!(path.last().unwrap().actor_id, target);
+                assert_chain(&path, &db);
+            }
+        }
+    }
+
+    #[test]
+    fn oracle_separate_components() {
+        // a pair of co-stars, and a chain of three
+        let db = MovieDB {
+            connections: MovieDBBuilder::get_connections(vec![
+                (1, 10),
+                (2, 10),
+                (3, 11),
+                (4, 11),
+                (4, 12),
+                (5, 12),
+            ]),
+            actors: HashMap::new(),
+            movies: HashMap::new(),
+        };
+        let oracle = DistanceOracle::build(&db);
+        assert_eq!(oracle.distance(3, 5, &db.connections), Some(2));
+        assert_eq!(oracle.distance(1, 5, &db.connections), None);
+        assert_eq!(oracle.path(1, 5, &db), None);
+        // an actor without credits
+        assert_eq!(oracle.distance(1, 6, &db.connections), None);
+        assert_eq!(oracle.distance(6, 6, &db.connections), Some(0));
+    }
+
+    #[test]
+    fn oracle_save_and_load() {
+        let db = make_test_db();
+        let oracle = DistanceOracle::build(&db);
+        let path = std::env::temp_dir().join(format!("oracle_{}.bin", std::process::id()));
+        oracle.save(&path, &db.connections).unwrap();
+        assert_eq!(
+            DistanceOracle::load(&path, &db.connections).unwrap(),
+            oracle
+        );
+
+        let other = MovieDBBuilder::get_connections(vec![(1, 10), (2, 10)]);
+        assert!(DistanceOracle::load(&path, &other).is_err());
+
+        // the same actors, but Tom Cruise is no longer in A Few Good Men
+        let data_file = PathBuf::from("data/new_small/connections.csv");
+        let mut pairs = MovieDBBuilder::read_actor_movie_pairs(&data_file).unwrap();
+        pairs.retain(|&pair| pair != (129, 104257));
+        let stale = make_db(pairs);
+        assert_eq!(all_actor_ids(&stale), all_actor_ids(&db));
+        let err = DistanceOracle::load(&path, &stale.connections).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        // labels of other credits give up rather than a wrong path
+        assert_eq!(oracle.path(129, 158, &stale), None);
+
+        // corrupt offsets or hubs are caught before they are used
+        let bytes = fs::read(&path).unwrap();
+        let offsets_start = 8 + 8 * (db.connections.num_actors() + 3);
+        let hubs_start = offsets_start + 8 * (db.connections.num_actors() + 1);
+        let corrupt = |at: usize, value: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[at..at + value.len()].copy_from_slice(value);
+            fs::write(&path, bytes).unwrap();
+            DistanceOracle::load(&path, &db.connections)
+                .unwrap_err()
+                .kind()
+        };
+        assert_eq!(
+            corrupt(offsets_start, &1u64.to_le_bytes()),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(
+            corrupt(offsets_start + 8, &u64::MAX.to_le_bytes()),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(
+            corrupt(offsets_start + 16, &0u64.to_le_bytes()),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(
+            corrupt(hubs_start, &u32::MAX.to_le_bytes()),
+            ErrorKind::InvalidData
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,458 @@
+use super::weighted::chain_to;
+use super::{
+    ActorId, Budget, Node, SearchError, SearchLimits, SearchOptions, SearchStats, join_paths,
+};
+use crate::data::{ActorIndex, MovieDB, MovieIndex};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+use tracing::{Span, field};
+
+/// How [`parallel_shortest_path`] spreads a search over threads.
+#[derive(Debug, Clone)]
+pub struct ParallelOptions {
+    /// Threads the levels are expanded on, shared by every search made with
+    /// these options.
+    pub pool: Arc<ThreadPool>,
+    /// Fewest frontier actors worth a thread of their own. Smaller levels
+    /// run on fewer threads, as handing them out costs more than expanding
+    /// a handful of actors.
+    pub min_actors_per_thread: usize,
+}
+
+impl ParallelOptions {
+    /// Options for searches on a new pool of `num_threads` threads.
+    pub fn new(num_threads: usize) -> Result<ParallelOptions, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|index| format!("search-{}", index))
+            .build()?;
+        Ok(ParallelOptions {
+            pool: Arc::new(pool),
+            min_actors_per_thread: 512,
+        })
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+}
+
+/// Finds a shortest chain of co-stars between two actors, like
+/// [`shortest_path`](super::shortest_path), with every level of the search
+/// expanded by several threads at once.
+///
+/// The search is level-synchronous: the frontier of the smaller tree is
+/// split between the threads, which claim the actors they reach in a shared
+/// atomic bitmap so every actor joins the tree once. The parents of the
+/// claimed actors are merged once the level is done, and the path is rebuilt
+/// from them when the trees meet. Which of several shortest paths comes back
+/// depends on the thread timing, but its length does not.
+///
+/// The threads check `limits` as they go, the deadline every thousand
+/// actors. Blocks the calling thread, so run it with `spawn_blocking` from
+/// async code.
+#[tracing::instrument(
+    skip(movie_db, options, limits, parallel),
+    fields(
+        nodes_created = field::Empty,
+        nodes_explored = field::Empty,
+        forward_frontier_sizes = field::Empty,
+        backward_frontier_sizes = field::Empty,
+        elapsed_ms = field::Empty,
+    )
+)]
+pub fn parallel_shortest_path(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+    parallel: &ParallelOptions,
+) -> Result<(Vec<Node>, SearchStats), SearchError> {
+    let start = Instant::now();
+    let mut stats = SearchStats::default();
+    let result = search(
+        source_actor_id,
+        target_actor_id,
+        movie_db,
+        options,
+        limits,
+        parallel,
+        &mut stats,
+    );
+    stats.elapsed = start.elapsed();
+    stats.record(&Span::current());
+    result.map(|path| (path, stats))
+}
+
+fn search(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+    parallel: &ParallelOptions,
+    stats: &mut SearchStats,
+) -> Result<Vec<Node>, SearchError> {
+    if !options.allows_actor(source_actor_id)
+        || !options.allows_actor(target_actor_id)
+        || !movie_db.connected(source_actor_id, target_actor_id)
+    {
+        return Err(SearchError::NoPath);
+    }
+    if source_actor_id == target_actor_id {
+        return Ok(vec![Node::new(source_actor_id, None, None)]);
+    }
+    let connections = &movie_db.connections;
+    // connected actors have credits
+    let mut forward = Tree::new(connections.actor_index(source_actor_id).unwrap(), movie_db);
+    let mut backward = Tree::new(connections.actor_index(target_actor_id).unwrap(), movie_db);
+
+    while !forward.frontier.is_empty() && !backward.frontier.is_empty() {
+        if limits.max_depth.is_some_and(|max_depth| {
+            stats.forward_frontier_sizes.len() + stats.backward_frontier_sizes.len() >= max_depth
+        }) {
+            return Err(SearchError::BudgetExceeded(Budget::Depth));
+        }
+
+        let expand_forward = forward.frontier.len() <= backward.frontier.len();
+        let (expanding, other) = if expand_forward {
+            stats.forward_frontier_sizes.push(forward.frontier.len());
+            (&mut forward, &backward)
+        } else {
+            stats.backward_frontier_sizes.push(backward.frontier.len());
+            (&mut backward, &forward)
+        };
+
+        let meeting = expand_level(
+            expanding,
+            other,
+            movie_db,
+            options,
+            limits,
+            parallel,
+            &mut stats.nodes_explored,
+        );
+        stats.nodes_created = forward.parents.len() + backward.parents.len() + 2;
+        if let Some(actor) = meeting? {
+            return Ok(join_paths(
+                chain_to(actor, &forward.parents, movie_db),
+                chain_to(actor, &backward.parents, movie_db),
+            ));
+        }
+    }
+    Err(SearchError::NoPath)
+}
+
+/// Set of actor or movie indices that several threads can add to at once.
+struct AtomicBitmap {
+    words: Vec<AtomicU64>,
+}
+
+impl AtomicBitmap {
+    fn new(len: usize) -> AtomicBitmap {
+        AtomicBitmap {
+            words: (0..len.div_ceil(64)).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    fn contains(&self, index: u32) -> bool {
+        let bit = 1 << (index % 64);
+        self.words[index as usize / 64].load(Ordering::Relaxed) & bit != 0
+    }
+
+    /// Adds the index. Returns whether it was missing, which is true for
+    /// exactly one of the threads adding the same index.
+    fn insert(&self, index: u32) -> bool {
+        let bit = 1 << (index % 64);
+        self.words[index as usize / 64].fetch_or(bit, Ordering::Relaxed) & bit == 0
+    }
+}
+
+/// One of the two search trees, rooted at the source or the target.
+struct Tree {
+    visited: AtomicBitmap,
+    /// Movies whose cast has been reached. Their whole cast joins the tree
+    /// the first time, so no movie is looked at twice.
+    expanded_movies: AtomicBitmap,
+    /// The actor and movie every actor but the root was reached through.
+    parents: HashMap<ActorIndex, (ActorIndex, MovieIndex)>,
+    frontier: Vec<ActorIndex>,
+}
+
+impl Tree {
+    fn new(root: ActorIndex, movie_db: &MovieDB) -> Tree {
+        let connections = &movie_db.connections;
+        let visited = AtomicBitmap::new(connections.num_actors());
+        visited.insert(root);
+        Tree {
+            visited,
+            expanded_movies: AtomicBitmap::new(connections.num_movies()),
+            parents: HashMap::new(),
+            frontier: vec![root],
+        }
+    }
+}
+
+/// The hops `(co_star, actor, movie)` by which part of a frontier reached
+/// new actors, and one of them the other tree holds already, if any.
+struct Reached {
+    hops: Vec<(ActorIndex, ActorIndex, MovieIndex)>,
+    meeting: Option<ActorIndex>,
+}
+
+/// Expands the whole frontier of `tree` by one hop on the threads of the
+/// pool, and queues the actors reached as its next frontier.
+///
+/// Returns an actor both trees hold as soon as they meet.
+fn expand_level(
+    tree: &mut Tree,
+    other: &Tree,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+    parallel: &ParallelOptions,
+    num_explored: &mut usize,
+) -> Result<Option<ActorIndex>, SearchError> {
+    let frontier = std::mem::take(&mut tree.frontier);
+    let num_threads = parallel
+        .num_threads()
+        .min(frontier.len() / parallel.min_actors_per_thread.max(1))
+        .max(1);
+    let chunk_size = frontier.len().div_ceil(num_threads).max(1);
+    let level = Level {
+        tree,
+        other,
+        movie_db,
+        options,
+        limits,
+        num_explored: AtomicUsize::new(*num_explored),
+        stop: AtomicBool::new(false),
+    };
+
+    let parts: Result<Vec<Reached>, SearchError> = if num_threads == 1 {
+        level.expand_chunk(&frontier).map(|part| vec![part])
+    } else {
+        parallel.pool.install(|| {
+            frontier
+                .par_chunks(chunk_size)
+                .map(|chunk| level.expand_chunk(chunk))
+                .collect()
+        })
+    };
+    *num_explored = level.num_explored.into_inner();
+    let parts = parts?;
+
+    let mut meeting = None;
+    for part in parts {
+        for (co_star, actor, movie) in part.hops {
+            tree.parents.insert(co_star, (actor, movie));
+            tree.frontier.push(co_star);
+        }
+        meeting = meeting.or(part.meeting);
+    }
+    Ok(meeting)
+}
+
+/// What the threads expanding one level share.
+struct Level<'a> {
+    tree: &'a Tree,
+    other: &'a Tree,
+    movie_db: &'a MovieDB,
+    options: &'a SearchOptions,
+    limits: &'a SearchLimits,
+    /// Actors expanded by the whole search so far.
+    num_explored: AtomicUsize,
+    /// Set once a thread has found where the trees meet or run into the
+    /// limits, so the others stop too.
+    stop: AtomicBool,
+}
+
+impl Level<'_> {
+    /// Expands the actors of `chunk`, stopping early once any thread has
+    /// found where the trees meet or run into the limits.
+    fn expand_chunk(&self, chunk: &[ActorIndex]) -> Result<Reached, SearchError> {
+        let (tree, other, movie_db, options) = (self.tree, self.other, self.movie_db, self.options);
+        let connections = &movie_db.connections;
+        let mut reached = Reached {
+            hops: Vec::new(),
+            meeting: None,
+        };
+        for &actor in chunk {
+            if self.stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let num_explored = self.num_explored.fetch_add(1, Ordering::Relaxed);
+            if let Err(err) = self.check_limits(num_explored) {
+                self.stop.store(true, Ordering::Relaxed);
+                return Err(err);
+            }
+            for &movie in connections.movies_of(actor) {
+                if !options.allows_movie(movie, movie_db) || !tree.expanded_movies.insert(movie) {
+                    continue;
+                }
+                for &co_star in connections.cast_of(movie) {
+                    // reading first keeps the threads from all writing to the
+                    // words of well known actors
+                    if tree.visited.contains(co_star)
+                        || !options.allows_actor(connections.actor_id(co_star))
+                        || !tree.visited.insert(co_star)
+                    {
+                        continue;
+                    }
+                    reached.hops.push((co_star, actor, movie));
+                    if reached.meeting.is_none() && other.visited.contains(co_star) {
+                        reached.meeting = Some(co_star);
+                        self.stop.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+        Ok(reached)
+    }
+
+    fn check_limits(&self, num_explored: usize) -> Result<(), SearchError> {
+        if num_explored.is_multiple_of(1000) {
+            self.limits.check_deadline()?;
+        }
+        self.limits.check(num_explored)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::shortest_path;
+    use crate::graph::test::{all_actor_ids, assert_chain, make_db, make_random_db, make_test_db};
+    use std::collections::HashSet;
+
+    /// Runs every level on up to `num_threads` threads, however small.
+    fn threads(num_threads: usize) -> ParallelOptions {
+        ParallelOptions {
+            min_actors_per_thread: 1,
+            ..ParallelOptions::new(num_threads).unwrap()
+        }
+    }
+
+    /// Checks the parallel search against the serial one between every
+    /// two actors of `actors`.
+    async fn assert_same_lengths(
+        movie_db: &MovieDB,
+        actors: &[ActorId],
+        options: &SearchOptions,
+        parallel: &ParallelOptions,
+    ) {
+        let limits = SearchLimits::default();
+        for &source in actors {
+            for &target in actors {
+                let serial = shortest_path(source, target, movie_db, options, &limits).await;
+                let found =
+                    parallel_shortest_path(source, target, movie_db, options, &limits, parallel);
+                assert_eq!(
+                    found.as_ref().map(|(path, _)| path.len()),
+                    serial.as_ref().map(|(path, _)| path.len())
+                );
+                if let Ok((path, _)) = found {
+                    assert_eq!(path.first().unwrap().actor_id, source);
+                    assert_eq!(path.last().unwrap().actor_id, target);
+                    assert_chain(&path, movie_db);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn parallel_matches_serial() {
+        let db = make_test_db();
+        let actors = all_actor_ids(&db);
+        for num_threads in [1, 2, 4] {
+            assert_same_lengths(
+                &db,
+                &actors,
+                &SearchOptions::default(),
+                &threads(num_threads),
+            )
+            .await;
+        }
+
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([158]),    // Tom Hanks
+            excluded_movies: HashSet::from([104257]), // A Few Good Men
+            ..Default::default()
+        };
+        assert_same_lengths(&db, &actors, &options, &threads(3)).await;
+    }
+
+    #[tokio::test]
+    async fn parallel_matches_serial_random() {
+        let db = make_random_db(2000);
+        let actors: Vec<ActorId> = all_actor_ids(&db).into_iter().step_by(40).collect();
+        assert_same_lengths(&db, &actors, &SearchOptions::default(), &threads(4)).await;
+        let options = SearchOptions {
+            max_cast_size: Some(3),
+            ..Default::default()
+        };
+        assert_same_lengths(&db, &actors, &options, &threads(4)).await;
+    }
+
+    #[test]
+    fn parallel_stats() {
+        let db = make_test_db();
+        let limits = SearchLimits::default();
+        let options = SearchOptions::default();
+        // Tom Cruise and Tom Hanks are two hops apart
+        let (path, stats) =
+            parallel_shortest_path(129, 158, &db, &options, &limits, &threads(2)).unwrap();
+        assert_eq!(path.len(), 3);
+        assert_eq!(
+            stats.forward_frontier_sizes.len() + stats.backward_frontier_sizes.len(),
+            2
+        );
+        assert!(stats.nodes_explored >= 2);
+    }
+
+    #[test]
+    fn parallel_limits() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        let limits = SearchLimits {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let path = parallel_shortest_path(129, 158, &db, &options, &limits, &threads(2));
+        assert_eq!(path, Err(SearchError::BudgetExceeded(Budget::Depth)));
+
+        let limits = SearchLimits::default();
+        limits.cancellation.cancel();
+        let path = parallel_shortest_path(129, 158, &db, &options, &limits, &threads(2));
+        assert_eq!(path, Err(SearchError::Cancelled));
+        // unconnected actors fail before any check
+        let path = parallel_shortest_path(129, 1, &db, &options, &limits, &threads(2));
+        assert_eq!(path, Err(SearchError::NoPath));
+
+        // 1 and 2 each lead a cast of 300, and the last of one cast made a
+        // movie with the last of the other, so the third level has 300
+        // actors and finds the path at its very end
+        let mut pairs = vec![(1, 10), (2, 11), (399, 12), (699, 12)];
+        pairs.extend((100..400).map(|actor_id| (actor_id, 10)));
+        pairs.extend((400..700).map(|actor_id| (actor_id, 11)));
+        let db = make_db(pairs);
+        let (path, _) =
+            parallel_shortest_path(1, 2, &db, &options, &SearchLimits::default(), &threads(4))
+                .unwrap();
+        assert_eq!(path.len(), 4);
+        let limits = SearchLimits {
+            max_nodes_explored: Some(50),
+            ..Default::default()
+        };
+        let path = parallel_shortest_path(1, 2, &db, &options, &limits, &threads(4));
+        assert_eq!(
+            path,
+            Err(SearchError::BudgetExceeded(Budget::NodesExplored))
+        );
+    }
+}
@@ -0,0 +1,662 @@
+use super::{
+    ActorId, MovieId, Node, SearchError, SearchLimits, SearchOptions, bidirectional_search,
+    index_path, join_paths, shortest_path,
+};
+use crate::data::{ActorIndex, MovieDB, MovieIndex};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// What a chain of co-stars found by [`via_path`] has to go through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Via {
+    Actor(ActorId),
+    /// A movie two consecutive actors of the chain were both in.
+    Movie(MovieId),
+}
+
+/// Finds a shortest chain of co-stars from the source to the target that
+/// goes through the `via` actor or movie, with no actor in it twice.
+///
+/// The chain is built from two shortest searches: from the source to the
+/// via actor and on to the target, or from either end to the nearest actor
+/// of the via movie. When those legs cross, a min-cost flow finds the
+/// shortest pair of legs that don't, or shows that there is none. Only goes
+/// through the actors and movies that `options` allows.
+///
+/// Every search gives up once it runs into `limits`, the min-cost flow
+/// included, which counts the nodes of its network it settles as explored.
+pub async fn via_path(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    via: Via,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+) -> Result<Vec<Node>, SearchError> {
+    let connections = &movie_db.connections;
+    match via {
+        Via::Actor(via_actor_id) => {
+            if !options.allows_actor(via_actor_id) {
+                return Err(SearchError::Excluded);
+            }
+            if via_actor_id == source_actor_id || via_actor_id == target_actor_id {
+                return shortest_path(source_actor_id, target_actor_id, movie_db, options, limits)
+                    .await
+                    .map(|(path, _)| path);
+            }
+        }
+        Via::Movie(via_movie_id) => {
+            let Some(movie) = connections.movie_index(via_movie_id) else {
+                // no chain can use it
+                return Err(SearchError::NoPath);
+            };
+            if !options.allows_movie(movie, movie_db) {
+                return Err(SearchError::Excluded);
+            }
+        }
+    }
+    if source_actor_id == target_actor_id {
+        return Err(SearchError::RepeatsActor);
+    }
+
+    let legs = match via {
+        Via::Actor(via_actor_id) => {
+            actor_legs(
+                source_actor_id,
+                target_actor_id,
+                via_actor_id,
+                movie_db,
+                options,
+                limits,
+            )
+            .await?
+        }
+        Via::Movie(via_movie_id) => {
+            movie_legs(
+                source_actor_id,
+                target_actor_id,
+                via_movie_id,
+                movie_db,
+                options,
+                limits,
+            )
+            .await?
+        }
+    };
+    if let Some(path) = legs {
+        return Ok(path);
+    }
+    // both ends are connected to the via actor or movie, or a leg would
+    // have failed
+    let source = connections.actor_index(source_actor_id).unwrap();
+    let target = connections.actor_index(target_actor_id).unwrap();
+    disjoint_legs(source, target, via, movie_db, options, limits)?.ok_or(SearchError::RepeatsActor)
+}
+
+/// Joins a shortest path to the via actor with one from it, or returns
+/// `None` if they share an actor.
+async fn actor_legs(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    via_actor_id: ActorId,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+) -> Result<Option<Vec<Node>>, SearchError> {
+    let (to_via, _) =
+        shortest_path(source_actor_id, via_actor_id, movie_db, options, limits).await?;
+    let (from_via, _) =
+        shortest_path(via_actor_id, target_actor_id, movie_db, options, limits).await?;
+    if crosses(&to_via, &from_via[1..]) {
+        return Ok(None);
+    }
+    let mut path = to_via;
+    path.extend_from_slice(&from_via[1..]);
+    index_path(&mut path);
+    Ok(Some(path))
+}
+
+/// Joins a shortest path from the source to the nearest actor of the via
+/// movie with one from the target to the nearest, or returns `None` if
+/// they share an actor.
+async fn movie_legs(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    via_movie_id: MovieId,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+) -> Result<Option<Vec<Node>>, SearchError> {
+    let cast: Vec<ActorId> = movie_db
+        .actors_of_movie(via_movie_id)
+        .filter(|&actor_id| options.allows_actor(actor_id))
+        .collect();
+    // the search behind nearest_actor, within the limits
+    let (from_source, _) = bidirectional_search(
+        &[source_actor_id],
+        &cast,
+        movie_db,
+        options,
+        limits,
+        |_, _, _| true,
+    )
+    .await;
+    let from_source = from_source?;
+    let (from_target, _) = bidirectional_search(
+        &[target_actor_id],
+        &cast,
+        movie_db,
+        options,
+        limits,
+        |_, _, _| true,
+    )
+    .await;
+    let from_target = from_target?;
+    if crosses(&from_source, &from_target) {
+        return Ok(None);
+    }
+    let mut forward = from_source;
+    let meeting = from_target.last().unwrap().actor_id;
+    forward.push(Node::new(meeting, None, Some(via_movie_id)));
+    Ok(Some(join_paths(forward, from_target)))
+}
+
+/// Whether an actor is on both paths.
+fn crosses(path: &[Node], other: &[Node]) -> bool {
+    let actors: HashSet<ActorId> = path.iter().map(|node| node.actor_id).collect();
+    other.iter().any(|node| actors.contains(&node.actor_id))
+}
+
+/// A node of the flow network of [`disjoint_legs`]. Every actor is split
+/// into an entry and an exit joined by an arc of capacity one, so at most
+/// one leg goes through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum FlowNode {
+    /// Where both legs start: the via actor, or the via movie.
+    Via,
+    In(ActorIndex),
+    Out(ActorIndex),
+    Movie(MovieIndex),
+    /// Where both legs end, past the source and the target.
+    Sink,
+}
+
+/// Distances from the via node used to reduce the costs of the residual
+/// network, `beyond` for the nodes without one.
+#[derive(Default)]
+struct Potential {
+    distances: HashMap<FlowNode, i64>,
+    beyond: i64,
+}
+
+impl Potential {
+    fn of(&self, node: FlowNode) -> i64 {
+        self.distances.get(&node).copied().unwrap_or(self.beyond)
+    }
+}
+
+/// The flow network where a hop is a path `Out(actor) -> Movie -> In(co_star)`
+/// costing one, and the via node feeds the movies of the via actor or the
+/// cast of the via movie. Sending two units from the via node to the sink
+/// at the least cost gives the shortest two legs without an actor in common.
+struct Network<'a> {
+    movie_db: &'a MovieDB,
+    options: &'a SearchOptions,
+    limits: &'a SearchLimits,
+    via: Via,
+    ends: [ActorIndex; 2],
+    /// Units on the arcs that carry any, the arcs of the network only.
+    flow: HashMap<(FlowNode, FlowNode), u32>,
+    /// Nodes settled by the searches over the residual network so far.
+    num_explored: usize,
+}
+
+impl Network<'_> {
+    fn flow(&self, from: FlowNode, to: FlowNode) -> u32 {
+        self.flow.get(&(from, to)).copied().unwrap_or_default()
+    }
+
+    fn allows_actor(&self, actor: ActorIndex) -> bool {
+        let actor_id = self.movie_db.connections.actor_id(actor);
+        // the via actor is the via node
+        self.options.allows_actor(actor_id) && self.via != Via::Actor(actor_id)
+    }
+
+    /// Arcs of the network out of `node`, with their cost and capacity.
+    fn arcs(&self, node: FlowNode) -> Vec<(FlowNode, i64, u32)> {
+        let connections = &self.movie_db.connections;
+        let movie_db = self.movie_db;
+        match node {
+            FlowNode::Via => match self.via {
+                Via::Actor(actor_id) => {
+                    let actor = connections.actor_index(actor_id).unwrap();
+                    connections
+                        .movies_of(actor)
+                        .iter()
+                        .filter(|&&movie| self.options.allows_movie(movie, movie_db))
+                        .map(|&movie| (FlowNode::Movie(movie), 1, 2))
+                        .collect()
+                }
+                Via::Movie(movie_id) => {
+                    let movie = connections.movie_index(movie_id).unwrap();
+                    connections
+                        .cast_of(movie)
+                        .iter()
+                        .filter(|&&actor| self.allows_actor(actor))
+                        .map(|&actor| (FlowNode::In(actor), 0, 1))
+                        .collect()
+                }
+            },
+            FlowNode::In(actor) => vec![(FlowNode::Out(actor), 0, 1)],
+            FlowNode::Out(actor) => {
+                let mut arcs: Vec<_> = connections
+                    .movies_of(actor)
+                    .iter()
+                    .filter(|&&movie| self.options.allows_movie(movie, movie_db))
+                    .map(|&movie| (FlowNode::Movie(movie), 1, 2))
+                    .collect();
+                if self.ends.contains(&actor) {
+                    arcs.push((FlowNode::Sink, 0, 1));
+                }
+                arcs
+            }
+            FlowNode::Movie(movie) => connections
+                .cast_of(movie)
+                .iter()
+                .filter(|&&actor| self.allows_actor(actor))
+                .map(|&actor| (FlowNode::In(actor), 0, 2))
+                .collect(),
+            FlowNode::Sink => Vec::new(),
+        }
+    }
+
+    /// Arcs of the residual network out of `node`: the arcs with capacity
+    /// left, and the arcs into `node` that carry flow, backwards at the
+    /// opposite cost.
+    fn residual_arcs(&self, node: FlowNode) -> Vec<(FlowNode, i64)> {
+        let connections = &self.movie_db.connections;
+        let mut arcs: Vec<(FlowNode, i64)> = self
+            .arcs(node)
+            .into_iter()
+            .filter(|&(to, _, capacity)| self.flow(node, to) < capacity)
+            .map(|(to, cost, _)| (to, cost))
+            .collect();
+        // arcs into the node that can carry flow, with their cost
+        let into: Vec<(FlowNode, i64)> = match node {
+            FlowNode::In(actor) => {
+                let mut into: Vec<_> = connections
+                    .movies_of(actor)
+                    .iter()
+                    .map(|&movie| (FlowNode::Movie(movie), 0))
+                    .collect();
+                into.push((FlowNode::Via, 0));
+                into
+            }
+            FlowNode::Out(actor) => vec![(FlowNode::In(actor), 0)],
+            FlowNode::Movie(movie) => {
+                let mut into: Vec<_> = connections
+                    .cast_of(movie)
+                    .iter()
+                    .map(|&actor| (FlowNode::Out(actor), 1))
+                    .collect();
+                into.push((FlowNode::Via, 1));
+                into
+            }
+            FlowNode::Via | FlowNode::Sink => Vec::new(),
+        };
+        arcs.extend(
+            into.into_iter()
+                .filter(|&(from, _)| self.flow(from, node) > 0)
+                .map(|(from, cost)| (from, -cost)),
+        );
+        arcs
+    }
+
+    /// Dijkstra's algorithm over the residual network, up to the sink, with
+    /// the costs reduced by `potential` so none is negative. Returns the
+    /// reduced distance and the node before every node reached.
+    fn cheapest_paths(
+        &mut self,
+        potential: &Potential,
+    ) -> Result<HashMap<FlowNode, (i64, Option<FlowNode>)>, SearchError> {
+        let mut reached = HashMap::from([(FlowNode::Via, (0, None))]);
+        let mut queue = BinaryHeap::from([Reverse((0, FlowNode::Via))]);
+        while let Some(Reverse((distance, node))) = queue.pop() {
+            if reached[&node].0 < distance {
+                continue;
+            }
+            if node == FlowNode::Sink {
+                break;
+            }
+            if self.num_explored.is_multiple_of(1000) {
+                self.limits.check_deadline()?;
+            }
+            self.limits.check(self.num_explored)?;
+            self.num_explored += 1;
+
+            for (next, cost) in self.residual_arcs(node) {
+                let next_distance = distance + cost + potential.of(node) - potential.of(next);
+                if reached
+                    .get(&next)
+                    .is_none_or(|&(known, _)| next_distance < known)
+                {
+                    reached.insert(next, (next_distance, Some(node)));
+                    queue.push(Reverse((next_distance, next)));
+                }
+            }
+        }
+        Ok(reached)
+    }
+
+    /// Sends one more unit from the via node to the sink along the
+    /// cheapest path left. Returns the potential for the next unit, or
+    /// `None` if the sink can't be reached.
+    fn augment(&mut self, potential: &Potential) -> Result<Option<Potential>, SearchError> {
+        let reached = self.cheapest_paths(potential)?;
+        let Some(&(to_sink, _)) = reached.get(&FlowNode::Sink) else {
+            return Ok(None);
+        };
+        let to_sink = to_sink + potential.of(FlowNode::Sink);
+        let mut node = FlowNode::Sink;
+        while let Some(previous) = reached[&node].1 {
+            // a residual arc is either an arc of the network or one walked
+            // backwards
+            if self.arcs(previous).iter().any(|&(to, _, _)| to == node)
+                && self.flow(node, previous) == 0
+            {
+                *self.flow.entry((previous, node)).or_default() += 1;
+            } else {
+                let flow = self.flow.get_mut(&(node, previous)).unwrap();
+                *flow -= 1;
+                if *flow == 0 {
+                    self.flow.remove(&(node, previous));
+                }
+            }
+            node = previous;
+        }
+        // nodes the search didn't settle are at least as far as the sink,
+        // and capping them there keeps the reduced costs non-negative
+        let distances = reached
+            .into_iter()
+            .map(|(node, (distance, _))| (node, (distance + potential.of(node)).min(to_sink)))
+            .collect();
+        Ok(Some(Potential {
+            distances,
+            beyond: to_sink,
+        }))
+    }
+
+    /// Takes one leg off the flow, as the actors from the via node to one
+    /// of the ends and the movie that reached each.
+    fn take_leg(&mut self) -> Vec<(Option<MovieIndex>, ActorIndex)> {
+        let mut leg = Vec::new();
+        let mut movie = None;
+        let mut node = FlowNode::Via;
+        while node != FlowNode::Sink {
+            let next = self
+                .arcs(node)
+                .into_iter()
+                .map(|(to, _, _)| to)
+                .find(|&to| self.flow(node, to) > 0)
+                .expect("flow out of every node it goes into");
+            let flow = self.flow.get_mut(&(node, next)).unwrap();
+            *flow -= 1;
+            if *flow == 0 {
+                self.flow.remove(&(node, next));
+            }
+            match next {
+                FlowNode::Movie(next_movie) => movie = Some(next_movie),
+                FlowNode::In(actor) => leg.push((movie.take(), actor)),
+                _ => {}
+            }
+            node = next;
+        }
+        leg
+    }
+}
+
+/// The shortest chain through `via` whose two legs have no actor in
+/// common, or `None` if every chain repeats an actor.
+fn disjoint_legs(
+    source: ActorIndex,
+    target: ActorIndex,
+    via: Via,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+    limits: &SearchLimits,
+) -> Result<Option<Vec<Node>>, SearchError> {
+    let connections = &movie_db.connections;
+    let mut network = Network {
+        movie_db,
+        options,
+        limits,
+        via,
+        ends: [source, target],
+        flow: HashMap::new(),
+        num_explored: 0,
+    };
+    let Some(potential) = network.augment(&Potential::default())? else {
+        return Ok(None);
+    };
+    if network.augment(&potential)?.is_none() {
+        return Ok(None);
+    }
+
+    let mut legs = [network.take_leg(), network.take_leg()];
+    if legs[0].last().unwrap().1 != source {
+        legs.swap(0, 1);
+    }
+    let [to_source, to_target] = legs;
+
+    // walk the leg to the source backwards, each actor reached through the
+    // movie of the hop after it
+    let mut path = vec![Node::new(connections.actor_id(source), None, None)];
+    for pair in to_source.windows(2).rev() {
+        let movie_id = pair[1].0.map(|movie| connections.movie_id(movie));
+        path.push(Node::new(connections.actor_id(pair[0].1), None, movie_id));
+    }
+    let via_movie = match via {
+        Via::Actor(via_actor_id) => {
+            let movie_id = to_source[0].0.map(|movie| connections.movie_id(movie));
+            path.push(Node::new(via_actor_id, None, movie_id));
+            to_target[0].0
+        }
+        Via::Movie(via_movie_id) => connections.movie_index(via_movie_id),
+    };
+    path.push(Node::new(
+        connections.actor_id(to_target[0].1),
+        None,
+        via_movie.map(|movie| connections.movie_id(movie)),
+    ));
+    for &(movie, actor) in &to_target[1..] {
+        let movie_id = movie.map(|movie| connections.movie_id(movie));
+        path.push(Node::new(connections.actor_id(actor), None, movie_id));
+    }
+    index_path(&mut path);
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::Budget;
+    use crate::graph::test::{actor_ids, make_db, make_test_db};
+
+    fn movie_ids(path: &[Node]) -> Vec<usize> {
+        path.iter()
+            .skip(1)
+            .map(|node| node.movie_id.unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn via_actor() {
+        let db = make_test_db();
+        let limits = SearchLimits::default();
+        let options = SearchOptions::default();
+        // Tom Cruise and Tom Hanks are linked through Kevin Bacon anyway
+        let path = via_path(129, 158, Via::Actor(102), &db, &options, &limits)
+            .await
+            .unwrap();
+        assert_eq!(actor_ids(&path), vec![129, 102, 158]);
+        assert_eq!(movie_ids(&path), vec![104257, 112384]);
+
+        // the via actor at one end changes nothing
+        let path = via_path(129, 158, Via::Actor(129), &db, &options, &limits)
+            .await
+            .unwrap();
+        assert_eq!(path.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn via_movie() {
+        let db = make_test_db();
+        let limits = SearchLimits::default();
+        let options = SearchOptions::default();
+        // Kevin Bacon and Tom Hanks were both in Apollo 13
+        let path = via_path(129, 158, Via::Movie(112384), &db, &options, &limits)
+            .await
+            .unwrap();
+        assert_eq!(actor_ids(&path), vec![129, 102, 158]);
+        assert_eq!(movie_ids(&path), vec![104257, 112384]);
+
+        let options = SearchOptions {
+            excluded_movies: HashSet::from([112384]),
+            ..Default::default()
+        };
+        let path = via_path(129, 158, Via::Movie(112384), &db, &options, &limits).await;
+        assert_eq!(path, Err(SearchError::Excluded));
+    }
+
+    #[tokio::test]
+    async fn via_actor_legs_cross() {
+        // the shortest legs from 1 to 2 and from 2 to 3 both go through 4,
+        // the only other chain through 2 goes through 5
+        let pairs = vec![
+            (1, 10),
+            (4, 10),
+            (4, 11),
+            (2, 11),
+            (4, 12),
+            (3, 12),
+            (2, 13),
+            (5, 13),
+            (5, 14),
+            (3, 14),
+        ];
+        let db = make_db(pairs);
+        let limits = SearchLimits::default();
+        let options = SearchOptions::default();
+        let path = via_path(1, 3, Via::Actor(2), &db, &options, &limits)
+            .await
+            .unwrap();
+        assert_eq!(actor_ids(&path), vec![1, 4, 2, 5, 3]);
+        assert_eq!(movie_ids(&path), vec![10, 11, 13, 14]);
+        assert_eq!(path[2].parent_index, Some(1));
+
+        // without 5 every chain through 2 goes through 4 twice
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([5]),
+            ..Default::default()
+        };
+        let path = via_path(1, 3, Via::Actor(2), &db, &options, &limits).await;
+        assert_eq!(path, Err(SearchError::RepeatsActor));
+    }
+
+    #[tokio::test]
+    async fn via_within_limits() {
+        let pairs = vec![
+            (1, 10),
+            (4, 10),
+            (4, 11),
+            (2, 11),
+            (4, 12),
+            (3, 12),
+            (2, 13),
+            (5, 13),
+            (5, 14),
+            (3, 14),
+        ];
+        let db = make_db(pairs);
+        let options = SearchOptions::default();
+        let limits = SearchLimits::default();
+        limits.cancellation.cancel();
+        let path = via_path(1, 3, Via::Actor(2), &db, &options, &limits).await;
+        assert_eq!(path, Err(SearchError::Cancelled));
+
+        // the min-cost flow stops too
+        let connections = &db.connections;
+        let (source, target) = (
+            connections.actor_index(1).unwrap(),
+            connections.actor_index(3).unwrap(),
+        );
+        let legs = disjoint_legs(source, target, Via::Actor(2), &db, &options, &limits);
+        assert_eq!(legs, Err(SearchError::Cancelled));
+        let limits = SearchLimits {
+            max_nodes_explored: Some(3),
+            ..Default::default()
+        };
+        let legs = disjoint_legs(source, target, Via::Actor(2), &db, &options, &limits);
+        assert_eq!(
+            legs,
+            Err(SearchError::BudgetExceeded(Budget::NodesExplored))
+        );
+    }
+
+    #[tokio::test]
+    async fn via_actor_reroutes() {
+        // the shortest legs from 2 to both ends go through 4, and the leg
+        // to 1 has to give it up for the one to 3, which has no other way
+        let pairs = vec![
+            (2, 10),
+            (4, 10),
+            (4, 11),
+            (1, 11),
+            (4, 12),
+            (3, 12),
+            (2, 13),
+            (5, 13),
+            (5, 14),
+            (6, 14),
+            (6, 15),
+            (1, 15),
+        ];
+        let db = make_db(pairs);
+        let limits = SearchLimits::default();
+        let path = via_path(1, 3, Via::Actor(2), &db, &SearchOptions::default(), &limits)
+            .await
+            .unwrap();
+        assert_eq!(actor_ids(&path), vec![1, 6, 5, 2, 4, 3]);
+        assert_eq!(movie_ids(&path), vec![15, 14, 13, 10, 12]);
+    }
+
+    #[tokio::test]
+    async fn via_movie_legs_cross() {
+        // 4 is the actor of movie 20 nearest to both ends, so one end has to
+        // go the long way to 6
+        let pairs = vec![
+            (1, 10),
+            (4, 10),
+            (4, 11),
+            (3, 11),
+            (4, 20),
+            (6, 20),
+            (6, 12),
+            (7, 12),
+            (7, 13),
+            (3, 13),
+        ];
+        let db = make_db(pairs);
+        let limits = SearchLimits::default();
+        let options = SearchOptions::default();
+        let path = via_path(1, 3, Via::Movie(20), &db, &options, &limits)
+            .await
+            .unwrap();
+        assert_eq!(actor_ids(&path), vec![1, 4, 6, 7, 3]);
+        assert_eq!(movie_ids(&path), vec![10, 20, 12, 13]);
+
+        // the chain can't come back to the same actor
+        let path = via_path(1, 1, Via::Movie(20), &db, &options, &limits).await;
+        assert_eq!(path, Err(SearchError::RepeatsActor));
+    }
+}
@@ -0,0 +1,281 @@
+use super::{ActorId, MovieId, Node, SearchError, SearchOptions, index_path};
+use crate::data::{ActorIndex, MovieDB, MovieIndex};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Prices one hop of a chain, from `actor_id` to `co_star_id` through
+/// `movie_id`.
+///
+/// Costs add up along the chain, and [`cheapest_path`] finds the chain with
+/// the lowest total. A hop is priced in both directions depending on which
+/// actor the search starts from, so costs that are symmetric in the two
+/// actors give the same chain either way.
+pub trait HopCost {
+    fn cost(
+        &self,
+        movie_db: &MovieDB,
+        movie_id: MovieId,
+        actor_id: ActorId,
+        co_star_id: ActorId,
+    ) -> u64;
+}
+
+/// Favours recent movies: a hop costs one plus the age of the movie in years
+/// at `reference_year`.
+#[derive(Debug, Clone, Copy)]
+pub struct PreferRecent {
+    pub reference_year: u32,
+}
+
+/// Age given to movies without a known release year.
+const UNKNOWN_MOVIE_AGE: u64 = 100;
+
+impl PreferRecent {
+    /// Measures ages from the release year of the newest movie in `movie_db`.
+    pub fn new(movie_db: &MovieDB) -> PreferRecent {
+        let reference_year = movie_db
+            .movies
+            .values()
+            .map(|movie| movie.year)
+            .max()
+            .unwrap_or_default();
+        PreferRecent { reference_year }
+    }
+}
+
+impl HopCost for PreferRecent {
+    fn cost(&self, movie_db: &MovieDB, movie_id: MovieId, _: ActorId, _: ActorId) -> u64 {
+        let age = match movie_db.movies.get(&movie_id) {
+            Some(movie) => self.reference_year.saturating_sub(movie.year) as u64,
+            None => UNKNOWN_MOVIE_AGE,
+        };
+        1 + age
+    }
+}
+
+/// Favours movies with small casts, where the actors more likely worked
+/// together: a hop costs the number of actors in the movie.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreferSmallCasts;
+
+impl HopCost for PreferSmallCasts {
+    fn cost(&self, movie_db: &MovieDB, movie_id: MovieId, _: ActorId, _: ActorId) -> u64 {
+        movie_db.actors_of_movie(movie_id).count() as u64
+    }
+}
+
+/// Favours well known actors: a hop costs more the fewer credits its two
+/// actors have.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreferProlificActors;
+
+/// Cost of an actor with a single credit; an actor with `n` credits costs
+/// `n` times less.
+const SINGLE_CREDIT_COST: u64 = 1000;
+
+impl HopCost for PreferProlificActors {
+    fn cost(&self, movie_db: &MovieDB, _: MovieId, actor_id: ActorId, co_star_id: ActorId) -> u64 {
+        // both actors have at least the credit for this movie
+        let actor_credits = movie_db.movies_of_actor(actor_id).count() as u64;
+        let co_star_credits = movie_db.movies_of_actor(co_star_id).count() as u64;
+        1 + SINGLE_CREDIT_COST / actor_credits + SINGLE_CREDIT_COST / co_star_credits
+    }
+}
+
+/// Finds the chain of co-stars between two actors with the lowest total
+/// `cost`, and returns it with that total.
+///
+/// This is Dijkstra's algorithm from the source, so unlike
+/// [`super::shortest_path`] it may return a chain with more hops when they
+/// are cheaper. The path only goes through the actors and movies that
+/// `options` allows.
+pub async fn cheapest_path<C>(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    cost: &C,
+    movie_db: &MovieDB,
+    options: &SearchOptions,
+) -> Result<(Vec<Node>, u64), SearchError>
+where
+    C: HopCost + ?Sized,
+{
+    if !options.allows_actor(source_actor_id) || !options.allows_actor(target_actor_id) {
+        return Err(SearchError::NoPath);
+    }
+    if source_actor_id == target_actor_id {
+        return Ok((vec![Node::new(source_actor_id, None, None)], 0));
+    }
+    if !movie_db.connected(source_actor_id, target_actor_id) {
+        return Err(SearchError::NoPath);
+    }
+    let connections = &movie_db.connections;
+    let (Some(source), Some(target)) = (
+        connections.actor_index(source_actor_id),
+        connections.actor_index(target_actor_id),
+    ) else {
+        // one of the actors has no credits
+        return Err(SearchError::NoPath);
+    };
+
+    let mut distances: HashMap<ActorIndex, u64> = HashMap::from([(source, 0)]);
+    let mut parents: HashMap<ActorIndex, (ActorIndex, MovieIndex)> = HashMap::new();
+    let mut queue = BinaryHeap::from([Reverse((0, source))]);
+    let mut num_explored: usize = 0;
+
+    while let Some(Reverse((distance, actor))) = queue.pop() {
+        if distance > distances[&actor] {
+            // stale entry, the actor was settled through a cheaper chain
+            continue;
+        }
+        if actor == target {
+            return Ok((chain_to(target, &parents, movie_db), distance));
+        }
+        // yield control to event loop
+        if num_explored.is_multiple_of(1000) {
+            tokio::task::yield_now().await;
+        }
+        num_explored += 1;
+
+        let actor_id = connections.actor_id(actor);
+        for &movie in connections.movies_of(actor) {
+            if !options.allows_movie(movie, movie_db) {
+                continue;
+            }
+            let movie_id = connections.movie_id(movie);
+            for &co_star in connections.cast_of(movie) {
+                let co_star_id = connections.actor_id(co_star);
+                if co_star == actor || !options.allows_actor(co_star_id) {
+                    continue;
+                }
+                let next_distance = distance + cost.cost(movie_db, movie_id, actor_id, co_star_id);
+                match distances.entry(co_star) {
+                    Entry::Occupied(entry) if *entry.get() <= next_distance => continue,
+                    Entry::Occupied(mut entry) => {
+                        entry.insert(next_distance);
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(next_distance);
+                    }
+                }
+                parents.insert(co_star, (actor, movie));
+                queue.push(Reverse((next_distance, co_star)));
+            }
+        }
+    }
+    Err(SearchError::NoPath)
+}
+
+/// Follows `parents` back from `actor` to the source of the search.
+pub(super) fn chain_to(
+    mut actor: ActorIndex,
+    parents: &HashMap<ActorIndex, (ActorIndex, MovieIndex)>,
+    movie_db: &MovieDB,
+) -> Vec<Node> {
+    let connections = &movie_db.connections;
+    let mut path = Vec::new();
+    while let Some(&(parent, movie)) = parents.get(&actor) {
+        let movie_id = connections.movie_id(movie);
+        path.push(Node::new(connections.actor_id(actor), None, Some(movie_id)));
+        actor = parent;
+    }
+    path.push(Node::new(connections.actor_id(actor), None, None));
+    path.reverse();
+    index_path(&mut path);
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::test::{actor_ids, make_db, make_test_db};
+    use crate::graph::{SearchLimits, shortest_path};
+
+    #[tokio::test]
+    async fn cheapest_small_casts_matches_hops() {
+        let db = make_test_db();
+        let options = SearchOptions::default();
+        // every movie in the small dataset has a cast of four
+        let (path, cost) = cheapest_path(144, 102, &PreferSmallCasts, &db, &options)
+            .await
+            .unwrap();
+        let shortest = shortest_path(144, 102, &db, &options, &SearchLimits::default())
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(path.len(), shortest.len());
+        assert_eq!(cost, 4 * (path.len() as u64 - 1));
+    }
+
+    #[tokio::test]
+    async fn cheapest_small_casts_detour() {
+        // 1 and 2 share a movie of ten, or are two small movies apart
+        let mut pairs = vec![(1, 10), (2, 10), (1, 11), (3, 11), (3, 12), (2, 12)];
+        pairs.extend((100..108).map(|actor_id| (actor_id, 10)));
+        let db = make_db(pairs);
+        let options = SearchOptions::default();
+
+        let (path, cost) = cheapest_path(1, 2, &PreferSmallCasts, &db, &options)
+            .await
+            .unwrap();
+        assert_eq!(actor_ids(&path), vec![1, 3, 2]);
+        assert_eq!(cost, 4);
+        let path = shortest_path(1, 2, &db, &options, &SearchLimits::default())
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(actor_ids(&path), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn cheapest_prefer_recent() {
+        let db = make_test_db();
+        // Gary Sinise and Tom Hanks were in Forrest Gump (1994) and Apollo 13
+        // (1995) together
+        let cost = PreferRecent::new(&db);
+        assert_eq!(cost.reference_year, 1995);
+        let (path, total) = cheapest_path(641, 158, &cost, &db, &SearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(path[1].movie_id, Some(112384));
+        assert_eq!(total, 1);
+    }
+
+    #[tokio::test]
+    async fn cheapest_prefer_prolific() {
+        // 1 and 4 are linked through 2 or 3, and 3 has more credits
+        let mut pairs = vec![
+            (1, 10),
+            (2, 10),
+            (2, 11),
+            (4, 11),
+            (1, 12),
+            (3, 12),
+            (3, 13),
+        ];
+        pairs.push((4, 13));
+        pairs.extend((14..20).map(|movie_id| (3, movie_id)));
+        let db = make_db(pairs);
+        let options = SearchOptions::default();
+
+        let (path, _) = cheapest_path(1, 4, &PreferProlificActors, &db, &options)
+            .await
+            .unwrap();
+        assert_eq!(actor_ids(&path), vec![1, 3, 4]);
+        let (path, _) = cheapest_path(4, 1, &PreferProlificActors, &db, &options)
+            .await
+            .unwrap();
+        assert_eq!(actor_ids(&path), vec![4, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn cheapest_no_path() {
+        let db = make_test_db();
+        let options = SearchOptions {
+            excluded_actors: [102].into(), // Kevin Bacon
+            ..Default::default()
+        };
+        let path = cheapest_path(129, 158, &PreferSmallCasts, &db, &options).await;
+        assert_eq!(path, Err(SearchError::NoPath));
+    }
+}
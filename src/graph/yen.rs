@@ -0,0 +1,213 @@
+use super::{
+    ActorId, MovieId, Node, SearchError, SearchLimits, SearchOptions, bidirectional_search,
+    index_path,
+};
+use crate::data::MovieDB;
+use std::collections::HashSet;
+
+/// Finds up to `k` shortest simple chains of co-stars between two actors,
+/// ranked by length, using Yen's algorithm.
+///
+/// No chain visits an actor twice. Chains count as different when they
+/// differ in an actor or in a movie linking two actors, so the runners-up
+/// include chains of the same length that go through other movies. Ties in
+/// length keep the order in which the chains were found.
+pub async fn k_shortest_paths(
+    source_actor_id: ActorId,
+    target_actor_id: ActorId,
+    k: usize,
+    movie_db: &MovieDB,
+) -> Result<Vec<Vec<Node>>, SearchError> {
+    if k == 0 {
+        return Ok(Vec::new());
+    }
+    let options = SearchOptions::default();
+    let limits = SearchLimits::default();
+    let first = bidirectional_search(
+        &[source_actor_id],
+        &[target_actor_id],
+        movie_db,
+        &options,
+        &limits,
+        |_, _, _| true,
+    )
+    .await
+    .0?;
+    let mut shortest = vec![first];
+    let mut candidates: Vec<Vec<Node>> = Vec::new();
+
+    while shortest.len() < k {
+        let previous = shortest.last().unwrap().clone();
+        // branch off the previous chain at every actor but the target
+        for spur_index in 0..previous.len() - 1 {
+            let spur_actor_id = previous[spur_index].actor_id;
+            let root = &previous[..=spur_index];
+
+            // hops leaving the root that an accepted chain already took
+            let used_hops: HashSet<(ActorId, MovieId, ActorId)> = shortest
+                .iter()
+                .filter(|path| path.len() > spur_index + 1 && path[..=spur_index] == *root)
+                .map(|path| {
+                    let next = &path[spur_index + 1];
+                    (spur_actor_id, next.movie_id.unwrap(), next.actor_id)
+                })
+                .collect();
+            // keep the chain simple
+            let root_actors: HashSet<ActorId> = root[..spur_index]
+                .iter()
+                .map(|node| node.actor_id)
+                .collect();
+
+            let allowed = |actor_id: ActorId, movie_id: MovieId, co_star_id: ActorId| {
+                !root_actors.contains(&actor_id)
+                    && !root_actors.contains(&co_star_id)
+                    && !used_hops.contains(&(actor_id, movie_id, co_star_id))
+                    && !used_hops.contains(&(co_star_id, movie_id, actor_id))
+            };
+            let (Ok(spur_path), _) = bidirectional_search(
+                &[spur_actor_id],
+                &[target_actor_id],
+                movie_db,
+                &options,
+                &limits,
+                allowed,
+            )
+            .await
+            else {
+                continue;
+            };
+
+            let mut candidate = root.to_vec();
+            candidate.extend(spur_path.into_iter().skip(1));
+            index_path(&mut candidate);
+            if !shortest.contains(&candidate) && !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+
+        // the first of the shortest candidates is the next chain
+        let Some(best) = candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(index, path)| (path.len(), *index))
+            .map(|(index, _)| index)
+        else {
+            break;
+        };
+        shortest.push(candidates.remove(best));
+    }
+    Ok(shortest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::MovieDBBuilder;
+    use crate::graph::test::{assert_chain, make_test_db};
+    use crate::graph::{SearchLimits, shortest_path};
+
+    fn hops(path: &[Node]) -> Vec<(usize, Option<usize>)> {
+        path.iter()
+            .map(|node| (node.actor_id, node.movie_id))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn k_shortest_cruise_hanks() {
+        let db = make_test_db();
+        let paths = k_shortest_paths(129, 158, 6, &db).await.unwrap();
+
+        // the best chain is the shortest path
+        let path = shortest_path(
+            129,
+            158,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap()
+        .0;
+        assert_eq!(paths[0], path);
+
+        // the runners-up take one more hop: a co-star of Tom Cruise in A Few
+        // Good Men before Kevin Bacon, or a co-star of Kevin Bacon in Apollo
+        // 13 after him. Gary Sinise shares two movies with Tom Hanks.
+        let runners_up: HashSet<_> = paths[1..].iter().map(|path| hops(path)).collect();
+        let expected = HashSet::from([
+            vec![
+                (129, None),
+                (193, Some(104257)),
+                (102, Some(104257)),
+                (158, Some(112384)),
+            ],
+            vec![
+                (129, None),
+                (197, Some(104257)),
+                (102, Some(104257)),
+                (158, Some(112384)),
+            ],
+            vec![
+                (129, None),
+                (102, Some(104257)),
+                (200, Some(112384)),
+                (158, Some(112384)),
+            ],
+            vec![
+                (129, None),
+                (102, Some(104257)),
+                (641, Some(112384)),
+                (158, Some(112384)),
+            ],
+            vec![
+                (129, None),
+                (102, Some(104257)),
+                (641, Some(112384)),
+                (158, Some(109830)),
+            ],
+        ]);
+        assert_eq!(runners_up, expected);
+    }
+
+    #[tokio::test]
+    async fn k_shortest_ranked_and_simple() {
+        let db = make_test_db();
+        let paths = k_shortest_paths(144, 197, 10, &db).await.unwrap();
+        assert_eq!(paths.len(), 10);
+
+        for (index, path) in paths.iter().enumerate() {
+            assert_eq!(path.first().unwrap().actor_id, 144);
+            assert_eq!(path.last().unwrap().actor_id, 197);
+            // ranked by length
+            if index > 0 {
+                assert!(paths[index - 1].len() <= path.len());
+            }
+            // no actor twice
+            let actors: HashSet<usize> = path.iter().map(|node| node.actor_id).collect();
+            assert_eq!(actors.len(), path.len());
+            assert_chain(path, &db);
+            // all different
+            assert!(!paths[..index].contains(path));
+        }
+    }
+
+    #[tokio::test]
+    async fn k_shortest_runs_out() {
+        // a chain of three actors only has one simple path end to end
+        let connections = MovieDBBuilder::get_connections(vec![(1, 10), (2, 10), (2, 11), (3, 11)]);
+        let db = MovieDB {
+            connections,
+            actors: Default::default(),
+            movies: Default::default(),
+        };
+        let paths = k_shortest_paths(1, 3, 5, &db).await.unwrap();
+        assert_eq!(paths.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn k_shortest_no_path() {
+        let db = make_test_db();
+        let paths = k_shortest_paths(129, 1, 3, &db).await;
+        assert_eq!(paths, Err(SearchError::NoPath));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::data::Actor;
+use crate::data::{Actor, Movie};
 use std::collections::{HashMap, HashSet};
 /// module for handline stdin inpt and otput for the program
 use std::io::{BufRead, Write};
@@ -16,6 +16,37 @@ pub fn get_actor_by_id(actors: &HashMap<usize, Actor>, id: usize) -> Option<Acto
     actors.get(&id).cloned()
 }
 
+/// Ids of the actors matching `query`, which is either an actor id or a full
+/// name in any case.
+pub fn find_actor_ids(actors: &HashMap<usize, Actor>, query: &str) -> HashSet<usize> {
+    if let Ok(id) = query.trim().parse::<usize>()
+        && actors.contains_key(&id)
+    {
+        return HashSet::from([id]);
+    }
+    let name = query.trim().to_lowercase();
+    get_actor_by_name(actors, &name)
+        .into_iter()
+        .map(|actor| actor.id)
+        .collect()
+}
+
+/// Ids of the movies matching `query`, which is either a movie id or a title
+/// in any case.
+pub fn find_movie_ids(movies: &HashMap<usize, Movie>, query: &str) -> HashSet<usize> {
+    if let Ok(id) = query.trim().parse::<usize>()
+        && movies.contains_key(&id)
+    {
+        return HashSet::from([id]);
+    }
+    let title = query.trim().to_lowercase();
+    movies
+        .values()
+        .filter(|movie| movie.title.to_lowercase() == title)
+        .map(|movie| movie.id)
+        .collect()
+}
+
 fn fuzzy_search_actor<'a, T>(actors: T, name: &str) -> Vec<&'a String>
 where
     T: Iterator<Item = &'a String>,
@@ -187,6 +218,30 @@ mod test {
         assert_eq!(result, actor.unwrap().id)
     }
 
+    #[test]
+    fn test_find_actor_ids() {
+        let actors = make_test_actors("data/new_small/actors.csv");
+        assert_eq!(find_actor_ids(&actors, "102"), HashSet::from([102]));
+        assert_eq!(find_actor_ids(&actors, "Kevin Bacon"), HashSet::from([102]));
+        assert_eq!(
+            find_actor_ids(&actors, "tom cruise"),
+            HashSet::from([1, 129])
+        );
+        assert!(find_actor_ids(&actors, "nobody").is_empty());
+    }
+
+    #[test]
+    fn test_find_movie_ids() {
+        let movie_file = PathBuf::from("data/new_small/movies.csv");
+        let movies = MovieDBBuilder::read_movies(&movie_file).unwrap();
+        assert_eq!(find_movie_ids(&movies, "112384"), HashSet::from([112384]));
+        assert_eq!(
+            find_movie_ids(&movies, "apollo 13"),
+            HashSet::from([112384])
+        );
+        assert!(find_movie_ids(&movies, "Apollo 14").is_empty());
+    }
+
     #[test]
     fn test_cli_id() {
         let actors = make_test_actors("data/new_small/actors.csv");
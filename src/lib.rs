@@ -1,5 +1,8 @@
+pub mod analysis;
 pub mod configuration;
 pub mod data;
+pub mod export;
 pub mod graph;
 pub mod interactive_io;
+pub mod query;
 pub mod webapp;
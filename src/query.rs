@@ -0,0 +1,175 @@
+//! Direct lookups on the credits of one or two actors, such as who they
+//! worked with, that need no search through the co-star graph.
+//!
+//! Actors without credits, or unknown to the data, have no co-stars and no
+//! movies.
+use crate::data::{ActorIndex, MovieDB, MovieIndex};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// An actor who worked with the queried actor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoStar {
+    pub actor_id: usize,
+    /// Movies the two of them were both in.
+    pub num_shared_movies: usize,
+}
+
+/// An actor who worked with both queried actors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommonCoStar {
+    pub actor_id: usize,
+    /// Movies they were in with the first actor.
+    pub num_movies_with_1: usize,
+    /// Movies they were in with the second actor.
+    pub num_movies_with_2: usize,
+}
+
+impl CommonCoStar {
+    pub fn num_shared_movies(&self) -> usize {
+        self.num_movies_with_1 + self.num_movies_with_2
+    }
+}
+
+/// Every actor who was in a movie with the actor, those they made the most
+/// movies with first, then by id.
+pub fn co_stars(movie_db: &MovieDB, actor_id: usize) -> Vec<CoStar> {
+    let connections = &movie_db.connections;
+    let mut co_stars: Vec<CoStar> = shared_movie_counts(movie_db, actor_id)
+        .into_iter()
+        .map(|(co_star, num_shared_movies)| CoStar {
+            actor_id: connections.actor_id(co_star),
+            num_shared_movies,
+        })
+        .collect();
+    co_stars.sort_unstable_by_key(|co_star| (Reverse(co_star.num_shared_movies), co_star.actor_id));
+    co_stars
+}
+
+/// Ids of the movies both actors were in, oldest first.
+pub fn shared_movies(movie_db: &MovieDB, actor_id_1: usize, actor_id_2: usize) -> Vec<usize> {
+    let movies_1: HashSet<MovieIndex> = movie_indices(movie_db, actor_id_1).collect();
+    let connections = &movie_db.connections;
+    let mut shared: Vec<usize> = movie_indices(movie_db, actor_id_2)
+        .filter(|movie| movies_1.contains(movie))
+        .map(|movie| connections.movie_id(movie))
+        .collect();
+    shared.sort_unstable_by_key(|movie_id| {
+        let year = movie_db.movies.get(movie_id).map(|movie| movie.year);
+        (year, *movie_id)
+    });
+    shared
+}
+
+/// Actors other than the two who were in a movie with each of them, those
+/// with the most movies with either first, then by id.
+pub fn common_co_stars(
+    movie_db: &MovieDB,
+    actor_id_1: usize,
+    actor_id_2: usize,
+) -> Vec<CommonCoStar> {
+    let connections = &movie_db.connections;
+    let counts_1 = shared_movie_counts(movie_db, actor_id_1);
+    let counts_2 = shared_movie_counts(movie_db, actor_id_2);
+    // neither count has the actor themselves, so the two are left out
+    let mut common: Vec<CommonCoStar> = counts_1
+        .into_iter()
+        .filter_map(|(co_star, num_movies_with_1)| {
+            Some(CommonCoStar {
+                actor_id: connections.actor_id(co_star),
+                num_movies_with_1,
+                num_movies_with_2: *counts_2.get(&co_star)?,
+            })
+        })
+        .collect();
+    common.sort_unstable_by_key(|co_star| (Reverse(co_star.num_shared_movies()), co_star.actor_id));
+    common
+}
+
+fn movie_indices(movie_db: &MovieDB, actor_id: usize) -> impl Iterator<Item = MovieIndex> + '_ {
+    let connections = &movie_db.connections;
+    connections
+        .actor_index(actor_id)
+        .into_iter()
+        .flat_map(|actor| connections.movies_of(actor))
+        .copied()
+}
+
+/// Number of movies the actor made with each of their co-stars.
+fn shared_movie_counts(movie_db: &MovieDB, actor_id: usize) -> HashMap<ActorIndex, usize> {
+    let connections = &movie_db.connections;
+    let Some(actor) = connections.actor_index(actor_id) else {
+        return HashMap::new();
+    };
+    let mut counts = HashMap::new();
+    for &movie in connections.movies_of(actor) {
+        for &co_star in connections.cast_of(movie) {
+            if co_star != actor {
+                *counts.entry(co_star).or_insert(0) += 1;
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::test::make_test_db;
+
+    #[test]
+    fn co_stars_by_shared_movies() {
+        let db = make_test_db();
+        let found = co_stars(&db, 158);
+        // Gary Sinise was in both Forrest Gump and Apollo 13 with Tom Hanks
+        assert_eq!(
+            found[0],
+            CoStar {
+                actor_id: 641,
+                num_shared_movies: 2
+            }
+        );
+        let rest: Vec<usize> = found[1..].iter().map(|co_star| co_star.actor_id).collect();
+        assert_eq!(rest, vec![102, 200, 398, 705]);
+        assert!(
+            found[1..]
+                .iter()
+                .all(|co_star| co_star.num_shared_movies == 1)
+        );
+
+        assert!(co_stars(&db, 1).is_empty());
+    }
+
+    #[test]
+    fn movies_made_together() {
+        let db = make_test_db();
+        // Forrest Gump (1994) before Apollo 13 (1995)
+        assert_eq!(shared_movies(&db, 158, 641), vec![109830, 112384]);
+        assert_eq!(shared_movies(&db, 102, 129), vec![104257]);
+        assert!(shared_movies(&db, 102, 705).is_empty());
+        assert!(shared_movies(&db, 102, 1).is_empty());
+    }
+
+    #[test]
+    fn co_stars_of_both() {
+        let db = make_test_db();
+        // Kevin Bacon met them on Apollo 13, Robin Wright on Forrest Gump
+        let common = common_co_stars(&db, 102, 705);
+        let actor_ids: Vec<usize> = common.iter().map(|co_star| co_star.actor_id).collect();
+        assert_eq!(actor_ids, vec![158, 641]);
+        assert_eq!(common[0].num_shared_movies(), 2);
+
+        // neither of the two counts as a common co-star
+        let common = common_co_stars(&db, 158, 641);
+        let actor_ids: Vec<usize> = common.iter().map(|co_star| co_star.actor_id).collect();
+        assert_eq!(actor_ids, vec![102, 200, 398, 705]);
+        assert_eq!(
+            common[0],
+            CommonCoStar {
+                actor_id: 102,
+                num_movies_with_1: 1,
+                num_movies_with_2: 1
+            }
+        );
+    }
+}
@@ -19,48 +19,64 @@ impl From<DbActor> for Actor {
     }
 }
 
-pub async fn query_actor(sqlite_pool: &SqlitePool, name: &str) -> Vec<Actor> {
+pub async fn query_actor(sqlite_pool: &SqlitePool, name: &str) -> Result<Vec<Actor>, sqlx::Error> {
     let query =
         sqlx::query_as::<_, DbActor>(r#"SELECT full_name FROM actors WHERE full_name = $1;"#)
             .bind(name);
 
-    query
-        .fetch_all(sqlite_pool)
-        .await
-        .unwrap()
-        .into_iter()
-        .map(Actor::from)
-        .collect()
+    let actors = query.fetch_all(sqlite_pool).await?;
+    Ok(actors.into_iter().map(Actor::from).collect())
+}
+
+/// Whether the `actor_centrality` table the searches rank actors by is
+/// there. Databases made before its migration don't have it.
+async fn has_centrality(sqlite_pool: &SqlitePool) -> Result<bool, sqlx::Error> {
+    let query = sqlx::query(
+        r#"SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'actor_centrality';"#,
+    );
+    Ok(query.fetch_optional(sqlite_pool).await?.is_some())
 }
 
-pub async fn query_similar_actor(sqlite_pool: &SqlitePool, name: &str) -> Vec<Actor> {
-    let query = sqlx::query_as::<_, DbActor>(
-        r#"SELECT id, full_name, birth_year FROM actors WHERE full_name LIKE '%' || $1 || '%' LIMIT 5;"#,
-    )
-    .bind(name);
+pub async fn query_similar_actor(
+    sqlite_pool: &SqlitePool,
+    name: &str,
+) -> Result<Vec<Actor>, sqlx::Error> {
+    let sql = if has_centrality(sqlite_pool).await? {
+        r#"SELECT actors.id, full_name, birth_year FROM actors
+        LEFT JOIN actor_centrality ON actor_centrality.id = actors.id
+        WHERE full_name LIKE '%' || $1 || '%'
+        ORDER BY actor_centrality.closeness IS NULL, actor_centrality.closeness DESC
+        LIMIT 5;"#
+    } else {
+        r#"SELECT id, full_name, birth_year FROM actors
+        WHERE full_name LIKE '%' || $1 || '%'
+        LIMIT 5;"#
+    };
+    let query = sqlx::query_as::<_, DbActor>(sql).bind(name);
 
-    query
-        .fetch_all(sqlite_pool)
-        .await
-        .unwrap()
-        .into_iter()
-        .map(Actor::from)
-        .collect()
+    let actors = query.fetch_all(sqlite_pool).await?;
+    Ok(actors.into_iter().map(Actor::from).collect())
 }
 
-pub async fn prefix_query_actors(sqlite_pool: &SqlitePool, name: &str) -> Vec<Actor> {
-    let query = sqlx::query_as::<_, DbActor>(
-        r#"SELECT id, full_name, birth_year FROM actors WHERE full_name MATCH '^' || $1 ||  ' *' LIMIT 5;"#,
-    )
-    .bind(name);
+pub async fn prefix_query_actors(
+    sqlite_pool: &SqlitePool,
+    name: &str,
+) -> Result<Vec<Actor>, sqlx::Error> {
+    let sql = if has_centrality(sqlite_pool).await? {
+        r#"SELECT actors.id, full_name, birth_year FROM actors
+        LEFT JOIN actor_centrality ON actor_centrality.id = actors.id
+        WHERE actors.full_name MATCH '^' || $1 ||  ' *'
+        ORDER BY actor_centrality.closeness IS NULL, actor_centrality.closeness DESC
+        LIMIT 5;"#
+    } else {
+        r#"SELECT id, full_name, birth_year FROM actors
+        WHERE full_name MATCH '^' || $1 ||  ' *'
+        LIMIT 5;"#
+    };
+    let query = sqlx::query_as::<_, DbActor>(sql).bind(name);
 
-    query
-        .fetch_all(sqlite_pool)
-        .await
-        .unwrap()
-        .into_iter()
-        .map(Actor::from)
-        .collect()
+    let actors = query.fetch_all(sqlite_pool).await?;
+    Ok(actors.into_iter().map(Actor::from).collect())
 }
 
 // tests
@@ -86,7 +102,7 @@ mod tests {
     async fn test_get_actor_by_substring() {
         let srk = srk();
         let pool = setup_actor_db();
-        let response = query_similar_actor(&pool, "shah rukh").await;
+        let response = query_similar_actor(&pool, "shah rukh").await.unwrap();
         assert!(response.contains(&srk));
     }
 
@@ -94,7 +110,50 @@ mod tests {
     async fn test_get_actor_by_prefix() {
         let srk = srk();
         let pool = setup_actor_db();
-        let response = prefix_query_actors(&pool, "shah rukh").await;
+        let response = prefix_query_actors(&pool, "shah rukh").await.unwrap();
         assert!(response.contains(&srk));
     }
+
+    /// An in-memory actor db with only the first migration, as databases
+    /// made before `actor_centrality` are.
+    async fn setup_db_without_centrality() -> SqlitePool {
+        // one connection, as every connection gets its own in-memory db
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::raw_sql(include_str!(
+            "../../migrations/20240523214128_make_full_text_index.sql"
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO actors (full_name, birth_year, id) VALUES ($1, $2, $3);")
+            .bind(&srk().full_name)
+            .bind(srk().birth_year)
+            .bind(srk().id as i64)
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn queries_without_centrality() {
+        let pool = setup_db_without_centrality().await;
+        let similar = query_similar_actor(&pool, "rukh").await.unwrap();
+        assert_eq!(similar, vec![srk()]);
+        let prefixed = prefix_query_actors(&pool, "shah rukh").await.unwrap();
+        assert_eq!(prefixed, vec![srk()]);
+
+        sqlx::raw_sql(include_str!(
+            "../../migrations/20261018120000_add_actor_centrality.sql"
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+        let similar = query_similar_actor(&pool, "rukh").await.unwrap();
+        assert_eq!(similar, vec![srk()]);
+    }
 }
@@ -1,9 +1,11 @@
 pub mod db_connection;
+pub mod path_cache;
 pub mod routes;
 pub mod startup;
 pub mod telemetry;
 
 pub use db_connection::*;
+pub use path_cache::*;
 pub use routes::*;
 pub use startup::*;
 pub use telemetry::*;
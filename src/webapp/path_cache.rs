@@ -0,0 +1,216 @@
+use crate::graph::{Node, SearchOptions, reverse_path};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Shortest paths found for recent searches, so popular pairs are searched
+/// once. Holds at most `capacity` paths and forgets the least recently used
+/// first. A path between two actors answers the search in either direction.
+pub struct PathCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// The two actors, lowest id first, and the search options in a hashable
+/// form.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    actor_ids: (usize, usize),
+    excluded_actors: Vec<usize>,
+    excluded_movies: Vec<usize>,
+    max_cast_size: Option<usize>,
+    min_year: Option<u32>,
+    max_year: Option<u32>,
+}
+
+#[derive(Default)]
+struct Entries {
+    /// Paths from the lower actor id to the higher, with when they were
+    /// last used.
+    paths: HashMap<CacheKey, (u64, Vec<Node>)>,
+    /// Keys by when they were last used, the least recent first.
+    by_use: BTreeMap<u64, CacheKey>,
+    clock: u64,
+}
+
+impl CacheKey {
+    fn new(actor_1: usize, actor_2: usize, options: &SearchOptions) -> CacheKey {
+        let mut excluded_actors: Vec<usize> = options.excluded_actors.iter().copied().collect();
+        excluded_actors.sort_unstable();
+        let mut excluded_movies: Vec<usize> = options.excluded_movies.iter().copied().collect();
+        excluded_movies.sort_unstable();
+        CacheKey {
+            actor_ids: (actor_1.min(actor_2), actor_1.max(actor_2)),
+            excluded_actors,
+            excluded_movies,
+            max_cast_size: options.max_cast_size,
+            min_year: options.min_year,
+            max_year: options.max_year,
+        }
+    }
+}
+
+impl Entries {
+    /// Marks the key as just used.
+    fn touch(&mut self, key: &CacheKey) {
+        self.clock += 1;
+        if let Some((last_used, _)) = self.paths.get_mut(key) {
+            self.by_use.remove(last_used);
+            *last_used = self.clock;
+            self.by_use.insert(self.clock, key.clone());
+        }
+    }
+}
+
+impl PathCache {
+    /// A cache of up to `capacity` paths. With 0 nothing is cached.
+    pub fn new(capacity: usize) -> PathCache {
+        PathCache {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The path from `actor_1` to `actor_2` found by an earlier search with
+    /// the same options, in either direction.
+    pub fn get(
+        &self,
+        actor_1: usize,
+        actor_2: usize,
+        options: &SearchOptions,
+    ) -> Option<Vec<Node>> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let key = CacheKey::new(actor_1, actor_2, options);
+        let mut entries = self.entries.lock().unwrap();
+        entries.touch(&key);
+        let Some((_, path)) = entries.paths.get(&key) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        if actor_1 <= actor_2 {
+            Some(path.clone())
+        } else {
+            Some(reverse_path(path))
+        }
+    }
+
+    /// Remembers a path from `actor_1` to `actor_2`, forgetting the least
+    /// recently used path when the cache is full.
+    pub fn insert(&self, actor_1: usize, actor_2: usize, options: &SearchOptions, path: &[Node]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = CacheKey::new(actor_1, actor_2, options);
+        let path = if actor_1 <= actor_2 {
+            path.to_vec()
+        } else {
+            reverse_path(path)
+        };
+        let mut entries = self.entries.lock().unwrap();
+        if let Some((_, cached)) = entries.paths.get_mut(&key) {
+            *cached = path;
+            entries.touch(&key);
+            return;
+        }
+        if entries.paths.len() == self.capacity
+            && let Some((_, oldest)) = entries.by_use.pop_first()
+        {
+            entries.paths.remove(&oldest);
+        }
+        entries.clock += 1;
+        let clock = entries.clock;
+        entries.by_use.insert(clock, key.clone());
+        entries.paths.insert(key, (clock, path));
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of paths cached.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Searches answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Searches the cache couldn't answer.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn path(actor_ids: &[usize]) -> Vec<Node> {
+        actor_ids
+            .iter()
+            .enumerate()
+            .map(|(index, &actor_id)| {
+                let movie_id = (index > 0).then_some(1000 + index);
+                Node::new(actor_id, index.checked_sub(1), movie_id)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn symmetric_lookups() {
+        let cache = PathCache::new(10);
+        let options = SearchOptions::default();
+        assert_eq!(cache.get(129, 158, &options), None);
+        cache.insert(158, 129, &options, &path(&[158, 102, 129]));
+
+        assert_eq!(cache.get(158, 129, &options), Some(path(&[158, 102, 129])));
+        let reversed = cache.get(129, 158, &options).unwrap();
+        let actor_ids: Vec<usize> = reversed.iter().map(|node| node.actor_id).collect();
+        assert_eq!(actor_ids, vec![129, 102, 158]);
+        assert_eq!(reversed[1].movie_id, Some(1002));
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+
+        // other options are another search
+        let options = SearchOptions {
+            excluded_actors: HashSet::from([102]),
+            ..Default::default()
+        };
+        assert_eq!(cache.get(129, 158, &options), None);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn least_recently_used_go_first() {
+        let cache = PathCache::new(2);
+        let options = SearchOptions::default();
+        cache.insert(1, 2, &options, &path(&[1, 2]));
+        cache.insert(3, 4, &options, &path(&[3, 4]));
+        // using 1-2 leaves 3-4 the least recently used
+        assert!(cache.get(2, 1, &options).is_some());
+        cache.insert(5, 6, &options, &path(&[5, 6]));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(3, 4, &options).is_none());
+        assert!(cache.get(1, 2, &options).is_some());
+        assert!(cache.get(5, 6, &options).is_some());
+
+        let off = PathCache::new(0);
+        off.insert(1, 2, &options, &path(&[1, 2]));
+        assert!(off.get(1, 2, &options).is_none());
+        assert!(off.is_empty());
+        assert_eq!(off.misses(), 0);
+    }
+}
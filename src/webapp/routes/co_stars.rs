@@ -0,0 +1,107 @@
+use crate::{
+    data::MovieDB,
+    query::{co_stars, common_co_stars, shared_movies},
+};
+use actix_web::{HttpResponse, Responder, web};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CoStarsOf {
+    actor: usize,
+}
+
+#[derive(Deserialize)]
+pub struct ActorPair {
+    actor_1: usize,
+    actor_2: usize,
+}
+
+#[derive(Serialize)]
+pub struct CoStarEntry {
+    actor_id: usize,
+    full_name: String,
+    num_shared_movies: usize,
+}
+
+#[derive(Serialize)]
+pub struct CommonCoStarEntry {
+    actor_id: usize,
+    full_name: String,
+    num_movies_with_1: usize,
+    num_movies_with_2: usize,
+}
+
+#[derive(Serialize)]
+pub struct MovieEntry {
+    movie_id: usize,
+    title: String,
+    year: u32,
+}
+
+pub async fn get_co_stars(
+    query: web::Query<CoStarsOf>,
+    movie_db: web::Data<MovieDB>,
+) -> impl Responder {
+    if !movie_db.actors.contains_key(&query.actor) {
+        return HttpResponse::NotFound().body("Actor not found");
+    }
+    let entries: Vec<CoStarEntry> = co_stars(&movie_db, query.actor)
+        .into_iter()
+        .map(|co_star| CoStarEntry {
+            full_name: movie_db.actors[&co_star.actor_id].full_name.clone(),
+            actor_id: co_star.actor_id,
+            num_shared_movies: co_star.num_shared_movies,
+        })
+        .collect();
+    HttpResponse::Ok().json(entries)
+}
+
+pub async fn get_shared_movies(
+    query: web::Query<ActorPair>,
+    movie_db: web::Data<MovieDB>,
+) -> impl Responder {
+    if let Some(response) = check_actors(&query, &movie_db) {
+        return response;
+    }
+    let entries: Vec<MovieEntry> = shared_movies(&movie_db, query.actor_1, query.actor_2)
+        .into_iter()
+        .map(|movie_id| {
+            let movie = &movie_db.movies[&movie_id];
+            MovieEntry {
+                movie_id,
+                title: movie.title.clone(),
+                year: movie.year,
+            }
+        })
+        .collect();
+    HttpResponse::Ok().json(entries)
+}
+
+pub async fn get_common_co_stars(
+    query: web::Query<ActorPair>,
+    movie_db: web::Data<MovieDB>,
+) -> impl Responder {
+    if let Some(response) = check_actors(&query, &movie_db) {
+        return response;
+    }
+    let entries: Vec<CommonCoStarEntry> = common_co_stars(&movie_db, query.actor_1, query.actor_2)
+        .into_iter()
+        .map(|co_star| CommonCoStarEntry {
+            full_name: movie_db.actors[&co_star.actor_id].full_name.clone(),
+            actor_id: co_star.actor_id,
+            num_movies_with_1: co_star.num_movies_with_1,
+            num_movies_with_2: co_star.num_movies_with_2,
+        })
+        .collect();
+    HttpResponse::Ok().json(entries)
+}
+
+fn check_actors(query: &ActorPair, movie_db: &MovieDB) -> Option<HttpResponse> {
+    if !movie_db.actors.contains_key(&query.actor_1) {
+        return Some(HttpResponse::NotFound().body("Actor 1 not found"));
+    }
+    if !movie_db.actors.contains_key(&query.actor_2) {
+        return Some(HttpResponse::NotFound().body("Actor 2 not found"));
+    }
+    None
+}
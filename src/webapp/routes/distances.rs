@@ -0,0 +1,55 @@
+use super::shortest_path::search_error_response;
+use crate::{
+    data::MovieDB,
+    graph::{Budget, SearchError, SearchOptions, distance_profile},
+};
+use actix_web::{HttpResponse, Responder, web};
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, timeout};
+
+#[derive(Deserialize)]
+pub struct OneActor {
+    actor: usize,
+}
+
+#[derive(Serialize)]
+pub struct DistanceHistogram {
+    actor_id: usize,
+    full_name: String,
+    /// Actors the actor can reach, themselves included.
+    num_reachable: usize,
+    mean_distance: Option<f64>,
+    /// Number of actors at each distance, indexed by the distance.
+    histogram: Vec<usize>,
+}
+
+pub async fn get_distances(
+    query: web::Query<OneActor>,
+    movie_db: web::Data<MovieDB>,
+) -> impl Responder {
+    let Some(actor) = movie_db.actors.get(&query.actor) else {
+        return HttpResponse::NotFound().body("Actor not found");
+    };
+
+    let result = timeout(
+        Duration::from_secs(60),
+        distance_profile(query.actor, &movie_db, &SearchOptions::default()),
+    )
+    .await;
+    let subject = format!("distances from {}", actor.full_name);
+    let profile = match result {
+        Ok(Ok(profile)) => profile,
+        Ok(Err(err)) => return search_error_response(err, &subject),
+        Err(_) => {
+            return search_error_response(SearchError::BudgetExceeded(Budget::Deadline), &subject);
+        }
+    };
+
+    HttpResponse::Ok().json(DistanceHistogram {
+        actor_id: actor.id,
+        full_name: actor.full_name.clone(),
+        num_reachable: profile.num_reachable(),
+        mean_distance: profile.mean_distance(),
+        histogram: profile.histogram,
+    })
+}
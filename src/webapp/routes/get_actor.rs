@@ -15,9 +15,10 @@ pub async fn get_actor_prefix(
     if name.len() < 4 {
         return HttpResponse::Ok().json(Vec::<Actor>::new());
     }
-    let actors = db_connection::prefix_query_actors(&pg_pool, &query.name).await;
-
-    HttpResponse::Ok().json(actors)
+    match db_connection::prefix_query_actors(&pg_pool, &query.name).await {
+        Ok(actors) => HttpResponse::Ok().json(actors),
+        Err(err) => actor_db_error(err),
+    }
 }
 
 pub async fn get_actor(
@@ -28,12 +29,22 @@ pub async fn get_actor(
     if name.len() < 4 {
         return HttpResponse::Ok().json(Vec::<String>::new());
     }
-    let actor = db_connection::query_actor(&pg_pool, &query.name).await;
+    let actor = match db_connection::query_actor(&pg_pool, &query.name).await {
+        Ok(actor) => actor,
+        Err(err) => return actor_db_error(err),
+    };
 
     if actor.is_empty() {
-        let similar_actors = db_connection::query_similar_actor(&pg_pool, &query.name).await;
-        HttpResponse::Ok().json(similar_actors)
+        match db_connection::query_similar_actor(&pg_pool, &query.name).await {
+            Ok(similar_actors) => HttpResponse::Ok().json(similar_actors),
+            Err(err) => actor_db_error(err),
+        }
     } else {
         HttpResponse::Ok().json(actor)
     }
 }
+
+fn actor_db_error(err: sqlx::Error) -> HttpResponse {
+    tracing::error!("Failed to query the actor db: {}", err);
+    HttpResponse::InternalServerError().body("Failed to look up actors")
+}
@@ -1,7 +1,19 @@
+pub mod co_stars;
+pub mod distances;
 pub mod get_actor;
 pub mod homepage;
+pub mod movie_path;
+pub mod nearest;
+pub mod path_cache_stats;
 pub mod shortest_path;
+pub mod subgraph;
 
+pub use co_stars::*;
+pub use distances::*;
 pub use get_actor::*;
 pub use homepage::*;
+pub use movie_path::*;
+pub use nearest::*;
+pub use path_cache_stats::*;
 pub use shortest_path::*;
+pub use subgraph::*;
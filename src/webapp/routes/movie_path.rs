@@ -0,0 +1,132 @@
+use super::shortest_path::search_error_response;
+use crate::{
+    data::MovieDB,
+    graph::{
+        Budget, Endpoint, EndpointPath, SearchError, SearchLimits, SearchOptions, endpoint_path,
+    },
+};
+use actix_web::{HttpResponse, Responder, web};
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, timeout};
+
+#[derive(Deserialize)]
+pub struct TwoMovies {
+    movie_1: usize,
+    movie_2: usize,
+}
+
+#[derive(Deserialize)]
+pub struct ActorAndMovie {
+    actor: usize,
+    movie: usize,
+}
+
+/// An actor or a movie along a chain, by name or title.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Link {
+    Actor(String),
+    Movie(String),
+}
+
+#[derive(Serialize)]
+pub struct Chain {
+    hops: usize,
+    /// The endpoints and everything between them, movies and actors taking
+    /// turns.
+    chain: Vec<Link>,
+}
+
+pub async fn get_movie_path(
+    query: web::Form<TwoMovies>,
+    movie_db: web::Data<MovieDB>,
+) -> impl Responder {
+    if !movie_db.movies.contains_key(&query.movie_1) {
+        return HttpResponse::NotFound().body("Movie 1 not found");
+    }
+    if !movie_db.movies.contains_key(&query.movie_2) {
+        return HttpResponse::NotFound().body("Movie 2 not found");
+    }
+    find_chain(
+        Endpoint::Movie(query.movie_1),
+        Endpoint::Movie(query.movie_2),
+        &movie_db,
+    )
+    .await
+}
+
+pub async fn get_actor_movie_path(
+    query: web::Form<ActorAndMovie>,
+    movie_db: web::Data<MovieDB>,
+) -> impl Responder {
+    if !movie_db.actors.contains_key(&query.actor) {
+        return HttpResponse::NotFound().body("Actor not found");
+    }
+    if !movie_db.movies.contains_key(&query.movie) {
+        return HttpResponse::NotFound().body("Movie not found");
+    }
+    find_chain(
+        Endpoint::Actor(query.actor),
+        Endpoint::Movie(query.movie),
+        &movie_db,
+    )
+    .await
+}
+
+async fn find_chain(source: Endpoint, target: Endpoint, movie_db: &MovieDB) -> HttpResponse {
+    let result = timeout(
+        Duration::from_secs(60),
+        endpoint_path(
+            source,
+            target,
+            movie_db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        ),
+    )
+    .await;
+    let err = match result {
+        Ok(Ok(found)) => {
+            return HttpResponse::Ok().json(Chain {
+                hops: found.num_hops(),
+                chain: chain_links(&found, movie_db),
+            });
+        }
+        Ok(Err(err)) => err,
+        Err(_) => SearchError::BudgetExceeded(Budget::Deadline),
+    };
+    let subject = format!(
+        "connection between {} and {}",
+        name(source, movie_db),
+        name(target, movie_db)
+    );
+    search_error_response(err, &subject)
+}
+
+fn name(endpoint: Endpoint, movie_db: &MovieDB) -> &str {
+    match endpoint {
+        Endpoint::Actor(actor_id) => &movie_db.actors[&actor_id].full_name,
+        Endpoint::Movie(movie_id) => &movie_db.movies[&movie_id].title,
+    }
+}
+
+/// The endpoints of the chain with every movie and actor in between.
+fn chain_links(found: &EndpointPath, movie_db: &MovieDB) -> Vec<Link> {
+    let title = |movie_id: usize| Link::Movie(movie_db.movies[&movie_id].title.clone());
+    let mut links = Vec::new();
+    links.extend(found.source_movie.map(title));
+    for node in &found.path {
+        // the first actor has no movie linking them to the one before
+        if let Some(movie_id) = node.movie_id {
+            links.push(title(movie_id));
+        }
+        links.push(Link::Actor(
+            movie_db.actors[&node.actor_id].full_name.clone(),
+        ));
+    }
+    // the same movie at both ends is listed once
+    if !found.path.is_empty() {
+        links.extend(found.target_movie.map(title));
+    }
+    links
+}
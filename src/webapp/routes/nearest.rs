@@ -0,0 +1,67 @@
+use super::shortest_path::{Connection, parse_ids, path_to_connections, search_error_response};
+use crate::{
+    data::MovieDB,
+    graph::{Budget, SearchError, SearchOptions, nearest_actor},
+};
+use actix_web::{HttpResponse, Responder, web};
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, timeout};
+
+#[derive(Deserialize)]
+pub struct ActorAndTargets {
+    actor: usize,
+    /// Comma separated ids of the actors to pick the nearest from.
+    targets: String,
+}
+
+#[derive(Serialize)]
+pub struct Nearest {
+    actor_id: usize,
+    full_name: String,
+    path: Vec<Connection>,
+}
+
+pub async fn get_nearest(
+    query: web::Form<ActorAndTargets>,
+    movie_db: web::Data<MovieDB>,
+) -> impl Responder {
+    if !movie_db.actors.contains_key(&query.actor) {
+        return HttpResponse::NotFound().body("Actor not found");
+    }
+    let Ok(targets) = parse_ids(Some(&query.targets)) else {
+        return HttpResponse::BadRequest().body("Targets must be comma separated numbers");
+    };
+    if targets.is_empty() {
+        return HttpResponse::BadRequest().body("No target actors given");
+    }
+    if let Some(target) = targets.iter().find(|id| !movie_db.actors.contains_key(id)) {
+        return HttpResponse::NotFound().body(format!("Target actor {} not found", target));
+    }
+    let mut targets: Vec<usize> = targets.into_iter().collect();
+    // search in a stable order, so ties always go the same way
+    targets.sort_unstable();
+
+    let actor_name = &movie_db.actors.get(&query.actor).unwrap().full_name;
+    let result = timeout(
+        Duration::from_secs(60),
+        nearest_actor(query.actor, &targets, &movie_db, &SearchOptions::default()),
+    )
+    .await;
+    let (nearest, path) = match result {
+        Ok(Ok(nearest)) => nearest,
+        Ok(Err(err)) => {
+            let subject = format!("connection between {} and any of the targets", actor_name);
+            return search_error_response(err, &subject);
+        }
+        Err(_) => {
+            let subject = format!("connection between {} and any of the targets", actor_name);
+            return search_error_response(SearchError::BudgetExceeded(Budget::Deadline), &subject);
+        }
+    };
+
+    HttpResponse::Ok().json(Nearest {
+        actor_id: nearest,
+        full_name: movie_db.actors.get(&nearest).unwrap().full_name.clone(),
+        path: path_to_connections(&path, &movie_db),
+    })
+}
@@ -0,0 +1,20 @@
+use crate::webapp::path_cache::PathCache;
+use actix_web::{HttpResponse, Responder, web};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct PathCacheStats {
+    capacity: usize,
+    len: usize,
+    hits: u64,
+    misses: u64,
+}
+
+pub async fn get_path_cache(path_cache: web::Data<PathCache>) -> impl Responder {
+    HttpResponse::Ok().json(PathCacheStats {
+        capacity: path_cache.capacity(),
+        len: path_cache.len(),
+        hits: path_cache.hits(),
+        misses: path_cache.misses(),
+    })
+}
@@ -1,12 +1,105 @@
-use crate::{data::MovieDB, graph::shortest_path};
+use crate::{
+    data::MovieDB,
+    graph::{
+        DistanceOracle, Node, ParallelOptions, SearchError, SearchLimits, SearchOptions,
+        SearchStats, Via, YearOrder, all_shortest_paths, chronological_path,
+        parallel_shortest_path, shortest_path, via_path,
+    },
+    webapp::path_cache::PathCache,
+};
 use actix_web::{HttpResponse, Responder, web};
 use serde::{Deserialize, Serialize};
-use tokio::time::{Duration, timeout};
+use std::collections::HashSet;
+use std::num::ParseIntError;
+use std::time::Instant;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, Span};
+
+/// Upper bound on `max_paths`, as the number of shortest paths between two
+/// well connected actors can be huge.
+const MAX_PATHS_LIMIT: usize = 100;
+
 #[derive(Deserialize)]
 pub struct TwoActors {
     actor_1: usize,
     actor_2: usize,
+    /// When set, respond with up to this many shortest paths instead of one.
+    max_paths: Option<usize>,
+    /// Comma separated ids of actors the path may not go through.
+    exclude_actors: Option<String>,
+    /// Comma separated ids of movies the path may not use.
+    exclude_movies: Option<String>,
+    /// Skip movies with a bigger cast.
+    max_cast_size: Option<usize>,
+    /// Hide movies released before this year.
+    min_year: Option<u32>,
+    /// Hide movies released after this year.
+    max_year: Option<u32>,
+    /// `non_decreasing` or `increasing` to only follow movies in release
+    /// order. Can't be combined with `max_paths`.
+    year_order: Option<String>,
+    /// Id of an actor the path has to go through.
+    via_actor: Option<usize>,
+    /// Id of a movie the path has to use.
+    via_movie: Option<usize>,
+}
+
+impl TwoActors {
+    fn search_options(&self) -> Result<SearchOptions, ParseIntError> {
+        Ok(SearchOptions {
+            excluded_actors: parse_ids(self.exclude_actors.as_deref())?,
+            excluded_movies: parse_ids(self.exclude_movies.as_deref())?,
+            max_cast_size: self.max_cast_size,
+            min_year: self.min_year,
+            max_year: self.max_year,
+        })
+    }
+
+    fn year_order(&self) -> Result<Option<YearOrder>, &'static str> {
+        match self.year_order.as_deref() {
+            None | Some("") => Ok(None),
+            Some("non_decreasing") => Ok(Some(YearOrder::NonDecreasing)),
+            Some("increasing") => Ok(Some(YearOrder::Increasing)),
+            Some(_) => Err("year_order must be non_decreasing or increasing"),
+        }
+    }
+
+    fn via(&self) -> Result<Option<Via>, &'static str> {
+        match (self.via_actor, self.via_movie) {
+            (None, None) => Ok(None),
+            (Some(actor_id), None) => Ok(Some(Via::Actor(actor_id))),
+            (None, Some(movie_id)) => Ok(Some(Via::Movie(movie_id))),
+            (Some(_), Some(_)) => Err("via_actor can't be combined with via_movie"),
+        }
+    }
+}
+
+pub(crate) fn parse_ids(ids: Option<&str>) -> Result<HashSet<usize>, ParseIntError> {
+    ids.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+#[derive(Deserialize)]
+pub struct Explain {
+    /// Respond with the search stats alongside the path.
+    #[serde(default)]
+    explain: bool,
 }
+
+#[derive(Serialize)]
+struct Explained<T> {
+    result: T,
+    /// How much work the search did. Only the plain shortest path search
+    /// keeps stats, so this is null for the others and for paths from the
+    /// distance oracle or the path cache.
+    stats: Option<SearchStats>,
+}
+
 #[derive(Serialize)]
 pub struct Connection {
     actor_1: String,
@@ -16,7 +109,11 @@ pub struct Connection {
 
 pub async fn get_shortest_path(
     query: web::Form<TwoActors>,
+    explain: web::Query<Explain>,
     movie_db: web::Data<MovieDB>,
+    oracle: web::Data<Option<DistanceOracle>>,
+    parallel: web::Data<Option<ParallelOptions>>,
+    path_cache: web::Data<PathCache>,
 ) -> impl Responder {
     if !movie_db.actors.contains_key(&query.actor_1) {
         return HttpResponse::NotFound().body("Actor 1 not found");
@@ -25,37 +122,196 @@ pub async fn get_shortest_path(
         return HttpResponse::NotFound().body("Actor 2 not found");
     }
 
+    let Ok(options) = query.search_options() else {
+        return HttpResponse::BadRequest().body("Excluded ids must be comma separated numbers");
+    };
+
+    let year_order = match query.year_order() {
+        Ok(year_order) => year_order,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    if year_order.is_some() && query.max_paths.is_some() {
+        return HttpResponse::BadRequest().body("year_order can't be combined with max_paths");
+    }
+
+    let via = match query.via() {
+        Ok(via) => via,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    if via.is_some() && (query.max_paths.is_some() || year_order.is_some()) {
+        return HttpResponse::BadRequest()
+            .body("via_actor and via_movie can't be combined with max_paths or year_order");
+    }
+    match via {
+        Some(Via::Actor(actor_id)) if !movie_db.actors.contains_key(&actor_id) => {
+            return HttpResponse::NotFound().body("Via actor not found");
+        }
+        Some(Via::Movie(movie_id)) if !movie_db.movies.contains_key(&movie_id) => {
+            return HttpResponse::NotFound().body("Via movie not found");
+        }
+        _ => {}
+    }
+
+    // no need to search when no chain of co-stars links them at all
+    if !movie_db.connected(query.actor_1, query.actor_2) {
+        let actor_1_name = &movie_db.actors.get(&query.actor_1).unwrap().full_name;
+        let actor_2_name = &movie_db.actors.get(&query.actor_2).unwrap().full_name;
+
+        return HttpResponse::NotFound().body(format!(
+            "{} and {} are not connected",
+            actor_1_name, actor_2_name
+        ));
+    }
+
+    // the oracle answers plain searches straight away
+    if let Some(oracle) = oracle.as_ref()
+        && query.max_paths.is_none()
+        && year_order.is_none()
+        && via.is_none()
+        && options.is_unrestricted()
+        && let Some(path) = oracle.path(query.actor_1, query.actor_2, &movie_db)
+    {
+        return respond(path_to_connections(&path, &movie_db), None, explain.explain);
+    }
+
     let timeout_duration = Duration::from_secs(60);
+    let cancellation = CancellationToken::new();
+    // actix drops this handler when the client disconnects, which stops the
+    // searches that take limits on their own task, or the parallel one at
+    // its next level
+    let _cancel_on_drop = cancellation.clone().drop_guard();
+    let limits = SearchLimits {
+        deadline: Some(Instant::now() + timeout_duration),
+        cancellation,
+        ..Default::default()
+    };
+    let (actor_1, actor_2) = (query.actor_1, query.actor_2);
 
-    let shortest_path_result = timeout(
-        timeout_duration,
-        shortest_path(query.actor_1, query.actor_2, &movie_db),
-    )
-    .await;
-
-    let path = match shortest_path_result {
-        Ok(Ok(path)) => path,
-        Ok(Err(_)) => {
-            let actor_1_name = &movie_db.actors.get(&query.actor_1).unwrap().full_name;
-            let actor_2_name = &movie_db.actors.get(&query.actor_2).unwrap().full_name;
-
-            return HttpResponse::InternalServerError().body(format!(
-                "No connection found between {} and {}",
-                actor_1_name, actor_2_name
-            ));
+    let shortest_paths_result = match (via, query.max_paths, year_order) {
+        (Some(via), _, _) => {
+            let search_db = movie_db.clone();
+            let runtime = tokio::runtime::Handle::current();
+            let span = Span::current();
+            // the min-cost flow never yields, so keep it off the async workers
+            let found = tokio::task::spawn_blocking(move || {
+                let _entered = span.enter();
+                runtime.block_on(via_path(
+                    actor_1, actor_2, via, &search_db, &options, &limits,
+                ))
+            })
+            .await;
+            match found {
+                Ok(result) => result.map(|path| (vec![path], None)),
+                Err(_) => return HttpResponse::InternalServerError().body("The search failed"),
+            }
+        }
+        (None, None, Some(year_order)) => {
+            chronological_path(actor_1, actor_2, year_order, &movie_db, &options, &limits)
+                .await
+                .map(|path| (vec![path], None))
         }
-        Err(_) => {
-            let actor_1_name = &movie_db.actors.get(&query.actor_1).unwrap().full_name;
-            let actor_2_name = &movie_db.actors.get(&query.actor_2).unwrap().full_name;
-
-            return HttpResponse::InternalServerError().body(format!(
-                "No connection found between {} and {} within 60s",
-                actor_1_name, actor_2_name
-            ));
+        (None, None, None) => match path_cache.get(actor_1, actor_2, &options) {
+            // a cached path did no search, so it has no stats
+            Some(path) => Ok((vec![path], None)),
+            None => {
+                let cached_options = options.clone();
+                let search_db = movie_db.clone();
+                let found = match parallel.as_ref().clone() {
+                    Some(parallel) => {
+                        let span = Span::current();
+                        tokio::task::spawn_blocking(move || {
+                            let _entered = span.enter();
+                            parallel_shortest_path(
+                                actor_1, actor_2, &search_db, &options, &limits, &parallel,
+                            )
+                        })
+                        .await
+                    }
+                    None => {
+                        let search = async move {
+                            shortest_path(actor_1, actor_2, &search_db, &options, &limits).await
+                        };
+                        tokio::spawn(search.in_current_span()).await
+                    }
+                };
+                let Ok(result) = found else {
+                    return HttpResponse::InternalServerError().body("The search failed");
+                };
+                result.map(|(path, stats)| {
+                    path_cache.insert(actor_1, actor_2, &cached_options, &path);
+                    (vec![path], Some(stats))
+                })
+            }
+        },
+        (None, Some(max_paths), _) => all_shortest_paths(
+            actor_1,
+            actor_2,
+            Some(max_paths.min(MAX_PATHS_LIMIT)),
+            &movie_db,
+            &options,
+            &limits,
+        )
+        .await
+        .map(|paths| (paths, None)),
+    };
+
+    let (paths, stats) = match shortest_paths_result {
+        Ok(found) => found,
+        Err(err) => {
+            let actor_1_name = &movie_db.actors.get(&actor_1).unwrap().full_name;
+            let actor_2_name = &movie_db.actors.get(&actor_2).unwrap().full_name;
+            let subject = format!("connection between {} and {}", actor_1_name, actor_2_name);
+            return search_error_response(err, &subject);
         }
     };
 
-    let mut shortest_path_json = Vec::new();
+    match query.max_paths {
+        None => respond(
+            path_to_connections(&paths[0], &movie_db),
+            stats,
+            explain.explain,
+        ),
+        Some(_) => {
+            let paths_json: Vec<Vec<Connection>> = paths
+                .iter()
+                .map(|path| path_to_connections(path, &movie_db))
+                .collect();
+            respond(paths_json, stats, explain.explain)
+        }
+    }
+}
+
+/// Responds to a search that failed: not found when there is nothing to
+/// find, unavailable when the search gave up, and with no body once the
+/// client has gone. `subject` says what was searched for, like
+/// "connection between A and B".
+pub(crate) fn search_error_response(err: SearchError, subject: &str) -> HttpResponse {
+    match err {
+        SearchError::NoPath => HttpResponse::NotFound().body(format!("No {} found", subject)),
+        SearchError::Excluded | SearchError::RepeatsActor => {
+            HttpResponse::NotFound().body(format!("No {} found: {}", subject, err))
+        }
+        SearchError::BudgetExceeded(_) => HttpResponse::ServiceUnavailable().body(format!(
+            "Gave up on the search for the {}: {}",
+            subject, err
+        )),
+        SearchError::Cancelled => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+/// Responds with `result`, or with `result` and `stats` side by side when
+/// the client asked for an explanation.
+fn respond<T: Serialize>(result: T, stats: Option<SearchStats>, explain: bool) -> HttpResponse {
+    if explain {
+        HttpResponse::Ok().json(Explained { result, stats })
+    } else {
+        HttpResponse::Ok().json(result)
+    }
+}
+
+/// One `Connection` per hop of the path.
+pub fn path_to_connections(path: &[Node], movie_db: &MovieDB) -> Vec<Connection> {
+    let mut connections = Vec::new();
 
     for node_index in 0..(path.len() - 1) {
         let actor_1 = movie_db.actors.get(&path[node_index].actor_id).unwrap();
@@ -71,17 +327,18 @@ pub async fn get_shortest_path(
             actor_2: actor_2.full_name.clone(),
         };
 
-        shortest_path_json.push(connection);
+        connections.push(connection);
     }
 
-    HttpResponse::Ok().json(shortest_path_json)
+    connections
 }
 
-// tests
 #[cfg(test)]
 mod tests {
-
+    use super::*;
     use crate::data::Actor;
+    use crate::graph::test::make_test_db;
+    use actix_web::{App, http::StatusCode, test};
     use sqlx::{SqlitePool, sqlite::SqliteConnectOptions, sqlite::SqlitePoolOptions};
 
     fn setup_actor_db() -> SqlitePool {
@@ -105,5 +362,74 @@ mod tests {
         }
     }
 
-    //
+    /// Posts `form` to `/shortest_path` of an app serving the small dataset,
+    /// with Tom Cruise's name capitalised and Shah Rukh Khan added, who has
+    /// no credits in it.
+    async fn post_shortest_path(form: &str) -> (StatusCode, String) {
+        let mut movie_db = make_test_db();
+        for actor in [srk(), tom_cruise()] {
+            movie_db.actors.insert(actor.id, actor);
+        }
+        let app = test::init_service(
+            App::new()
+                .route("/shortest_path", web::post().to(get_shortest_path))
+                .app_data(web::Data::new(setup_actor_db()))
+                .app_data(web::Data::new(movie_db))
+                .app_data(web::Data::new(None::<DistanceOracle>))
+                .app_data(web::Data::new(None::<ParallelOptions>))
+                .app_data(web::Data::new(PathCache::new(10))),
+        )
+        .await;
+        let request = test::TestRequest::post()
+            .uri("/shortest_path")
+            .insert_header(("content-type", "application/x-www-form-urlencoded"))
+            .set_payload(form.to_string())
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        let status = response.status();
+        let body = test::read_body(response).await;
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn cruise_hanks() {
+        let (status, body) = post_shortest_path("actor_1=129&actor_2=158").await;
+        assert_eq!(status, StatusCode::OK);
+        let connections: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections[0]["actor_1"], tom_cruise().full_name);
+        assert_eq!(connections[1]["actor_2"], "tom hanks");
+
+        let (status, body) = post_shortest_path("actor_1=129&actor_2=158&max_paths=5").await;
+        assert_eq!(status, StatusCode::OK);
+        let paths: Vec<Vec<serde_json::Value>> = serde_json::from_str(&body).unwrap();
+        assert!(!paths.is_empty() && paths.iter().all(|path| path.len() == 2));
+    }
+
+    #[actix_web::test]
+    async fn no_path_responses() {
+        let (status, body) = post_shortest_path("actor_1=451321&actor_2=129").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, "Shah Rukh Khan and Tom Cruise are not connected");
+
+        // Kevin Bacon is the only link between Tom Cruise and Tom Hanks
+        let (status, body) = post_shortest_path("actor_1=129&actor_2=158&exclude_actors=102").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, "No connection between Tom Cruise and tom hanks found");
+
+        let (status, body) = post_shortest_path("actor_1=129&actor_2=1").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, "Actor 2 not found");
+    }
+
+    #[actix_web::test]
+    async fn bad_requests() {
+        let (status, _) = post_shortest_path("actor_1=129&actor_2=158&year_order=backwards").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = post_shortest_path("actor_1=129&actor_2=158&exclude_movies=x").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) =
+            post_shortest_path("actor_1=129&actor_2=158&via_actor=102&via_movie=112384").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
 }
@@ -0,0 +1,107 @@
+use super::shortest_path::parse_ids;
+use crate::data::MovieDB;
+use actix_web::{HttpResponse, Responder, web};
+use serde::{Deserialize, Serialize};
+
+/// Hops around the actors when the query doesn't say.
+const DEFAULT_HOPS: u32 = 1;
+/// Upper bound on `hops`, as a few hops from anyone reach most actors.
+const MAX_HOPS: u32 = 3;
+/// Nodes in a subgraph when the query doesn't say.
+const DEFAULT_MAX_NODES: usize = 500;
+/// Upper bound on `max_nodes`, about what a browser can lay out.
+const MAX_NODES_LIMIT: usize = 5000;
+
+#[derive(Deserialize)]
+pub struct SubgraphQuery {
+    /// Comma separated ids of the actors at the centre.
+    actors: String,
+    hops: Option<u32>,
+    max_nodes: Option<usize>,
+}
+
+/// An actor or a movie, with an id unique among both.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GraphNode {
+    Actor { id: String, name: String, hops: u32 },
+    Movie { id: String, name: String, year: u32 },
+}
+
+/// A credit, linking an actor to a movie by their node ids.
+#[derive(Serialize)]
+pub struct GraphLink {
+    source: String,
+    target: String,
+}
+
+#[derive(Serialize)]
+pub struct GraphJson {
+    nodes: Vec<GraphNode>,
+    links: Vec<GraphLink>,
+}
+
+pub async fn get_subgraph(
+    query: web::Query<SubgraphQuery>,
+    movie_db: web::Data<MovieDB>,
+) -> impl Responder {
+    let Ok(actors) = parse_ids(Some(&query.actors)) else {
+        return HttpResponse::BadRequest().body("Actors must be comma separated numbers");
+    };
+    if actors.is_empty() {
+        return HttpResponse::BadRequest().body("No actors given");
+    }
+    if let Some(actor) = actors.iter().find(|id| !movie_db.actors.contains_key(id)) {
+        return HttpResponse::NotFound().body(format!("Actor {} not found", actor));
+    }
+    let actors: Vec<usize> = actors.into_iter().collect();
+    let hops = query.hops.unwrap_or(DEFAULT_HOPS).min(MAX_HOPS);
+    let max_nodes = query
+        .max_nodes
+        .unwrap_or(DEFAULT_MAX_NODES)
+        .min(MAX_NODES_LIMIT);
+
+    // a few hops from a well connected actor visit a lot of the graph
+    let db = movie_db.clone();
+    let result = web::block(move || db.subgraph(&actors, hops, max_nodes)).await;
+    let subgraph = match result {
+        Ok(Ok(subgraph)) => subgraph,
+        Ok(Err(err)) => return HttpResponse::BadRequest().body(err),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let mut nodes: Vec<GraphNode> = subgraph
+        .actors
+        .into_iter()
+        .map(|(actor_id, hops)| GraphNode::Actor {
+            id: actor_node(actor_id),
+            name: movie_db.actors[&actor_id].full_name.clone(),
+            hops,
+        })
+        .collect();
+    nodes.extend(subgraph.movies.into_iter().map(|movie_id| {
+        let movie = &movie_db.movies[&movie_id];
+        GraphNode::Movie {
+            id: movie_node(movie_id),
+            name: movie.title.clone(),
+            year: movie.year,
+        }
+    }));
+    let links = subgraph
+        .credits
+        .into_iter()
+        .map(|(actor_id, movie_id)| GraphLink {
+            source: actor_node(actor_id),
+            target: movie_node(movie_id),
+        })
+        .collect();
+    HttpResponse::Ok().json(GraphJson { nodes, links })
+}
+
+fn actor_node(actor_id: usize) -> String {
+    format!("actor/{}", actor_id)
+}
+
+fn movie_node(movie_id: usize) -> String {
+    format!("movie/{}", movie_id)
+}
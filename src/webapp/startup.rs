@@ -1,7 +1,12 @@
 use crate::configuration::{DatabaseSettings, Settings};
 use crate::data::{MovieDB, MovieDBBuilder};
+use crate::graph::{DistanceOracle, ORACLE_FILE, ParallelOptions};
+use crate::webapp::path_cache::PathCache;
 use crate::webapp::routes::shortest_path::get_shortest_path;
-use crate::webapp::routes::{get_actor, get_actor_prefix};
+use crate::webapp::routes::{
+    get_actor, get_actor_movie_path, get_actor_prefix, get_co_stars, get_common_co_stars,
+    get_distances, get_movie_path, get_nearest, get_path_cache, get_shared_movies, get_subgraph,
+};
 // use actix_cors::Cors;
 use actix_files as fs;
 use actix_web::{App, HttpServer, dev::Server, web};
@@ -31,8 +36,25 @@ impl Application {
         let port = listener.local_addr().unwrap().port();
 
         let movie_db = build_movie_db(&configuration.movie_data.file_path);
-
-        let server = run(listener, connection_pool, movie_db)?;
+        let oracle = load_oracle(&configuration.movie_data.file_path, &movie_db);
+
+        // one pool of search threads for every request
+        let search_threads = configuration.application.search_threads;
+        let parallel = (search_threads > 1)
+            .then(|| ParallelOptions::new(search_threads))
+            .transpose()
+            .map_err(std::io::Error::other)?;
+
+        let path_cache = PathCache::new(configuration.application.path_cache_size);
+
+        let server = run(
+            listener,
+            connection_pool,
+            movie_db,
+            oracle,
+            parallel,
+            path_cache,
+        )?;
 
         Ok(Self { port, server })
     }
@@ -54,9 +76,15 @@ pub fn run(
     listener: TcpListener,
     connection_pool: SqlitePool,
     movie_db: MovieDB,
+    oracle: Option<DistanceOracle>,
+    parallel: Option<ParallelOptions>,
+    path_cache: PathCache,
 ) -> Result<Server, std::io::Error> {
     let connection_pool = web::Data::new(connection_pool);
     let movie_db = web::Data::new(movie_db);
+    let oracle = web::Data::new(oracle);
+    let parallel = web::Data::new(parallel);
+    let path_cache = web::Data::new(path_cache);
     let server = HttpServer::new(move || {
         App::new()
             .wrap(TracingLogger::default())
@@ -71,10 +99,22 @@ pub fn run(
             .route("/actor", web::get().to(get_actor))
             .route("/actor_prefix", web::post().to(get_actor_prefix))
             .route("/shortest_path", web::post().to(get_shortest_path))
+            .route("/path_cache", web::get().to(get_path_cache))
+            .route("/nearest", web::post().to(get_nearest))
+            .route("/movie_path", web::post().to(get_movie_path))
+            .route("/actor_movie_path", web::post().to(get_actor_movie_path))
+            .route("/distances", web::get().to(get_distances))
+            .route("/co_stars", web::get().to(get_co_stars))
+            .route("/shared_movies", web::get().to(get_shared_movies))
+            .route("/common_co_stars", web::get().to(get_common_co_stars))
+            .route("/subgraph", web::get().to(get_subgraph))
             .route("/", web::get().to(homepage))
             .service(fs::Files::new("/static", "./static"))
             .app_data(connection_pool.clone())
             .app_data(movie_db.clone())
+            .app_data(oracle.clone())
+            .app_data(parallel.clone())
+            .app_data(path_cache.clone())
     })
     .listen(listener)?
     .run();
@@ -82,6 +122,30 @@ pub fn run(
     Ok(server)
 }
 
+/// Loads the distance oracle saved next to the data, if there is one that
+/// matches it. Without one, every search runs a BFS.
+fn load_oracle(data_dir: &Path, movie_db: &MovieDB) -> Option<DistanceOracle> {
+    let oracle_file = data_dir.join(ORACLE_FILE);
+    if !oracle_file.exists() {
+        tracing::info!("No distance oracle in {:?}, searching with BFS", data_dir);
+        return None;
+    }
+    match DistanceOracle::load(&oracle_file, &movie_db.connections) {
+        Ok(oracle) => {
+            tracing::info!(
+                "Loaded a distance oracle with {} label entries ({:.1} MiB)",
+                oracle.num_entries(),
+                oracle.heap_size() as f64 / (1024.0 * 1024.0)
+            );
+            Some(oracle)
+        }
+        Err(err) => {
+            tracing::warn!("Ignoring the distance oracle in {:?}: {}", oracle_file, err);
+            None
+        }
+    }
+}
+
 fn build_movie_db(data_dir: &Path) -> MovieDB {
     let actor_file = data_dir.join("actors.csv");
     let actor_read_handler = thread::spawn(move || {
@@ -96,18 +160,24 @@ fn build_movie_db(data_dir: &Path) -> MovieDB {
     let connections_read_handler = thread::spawn(move || {
         let connections = MovieDBBuilder::read_actor_movie_pairs(&connections_file)
             .expect("Failed to read connections file");
-        MovieDBBuilder::get_actor_movie_maps(connections)
+        MovieDBBuilder::get_connections(connections)
     });
 
     let actors = actor_read_handler.join().unwrap();
 
     let movies = movie_read_handler.join().unwrap();
-    let (actor_to_movies, movie_to_actors) = connections_read_handler.join().unwrap();
+    let connections = connections_read_handler.join().unwrap();
+    tracing::info!("{}", connections.memory_summary());
+    let components = connections.components();
+    tracing::info!(
+        "{} connected components, the largest with {} actors",
+        components.num_components(),
+        components.sizes().first().copied().unwrap_or_default()
+    );
 
     MovieDB {
         actors,
         movies,
-        actor_to_movies,
-        movie_to_actors,
+        connections,
     }
 }
@@ -1,8 +1,15 @@
 #[cfg(test)]
 mod test {
+    use celeb_shortest_distance::data::synthetic::random_credits;
     use celeb_shortest_distance::data::{MovieDB, MovieDBBuilder};
-    use celeb_shortest_distance::graph::shortest_path;
+    use celeb_shortest_distance::graph::{SearchLimits, SearchOptions, shortest_path};
+    use std::collections::HashMap;
     use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
+
+    /// How long a search on a graph the size of `data/new_large` may take,
+    /// even in a debug build.
+    const SEARCH_TIME_LIMIT: Duration = Duration::from_secs(10);
 
     fn make_db(data_dir: &Path) -> MovieDB {
         let actor_file = data_dir.join("actors.csv");
@@ -11,12 +18,11 @@ mod test {
         let movies = MovieDBBuilder::read_movies(&movie_file).unwrap();
         let actor_movie_file = data_dir.join("connections.csv");
         let pairs = MovieDBBuilder::read_actor_movie_pairs(&actor_movie_file).unwrap();
-        let (actor_to_movies, movie_to_actors) = MovieDBBuilder::get_actor_movie_maps(pairs);
+        let connections = MovieDBBuilder::get_connections(pairs);
         MovieDB {
             actors,
             movies,
-            actor_to_movies,
-            movie_to_actors,
+            connections,
         }
     }
 
@@ -24,10 +30,6 @@ mod test {
         let data_dir = PathBuf::from("data/new_small");
         make_db(&data_dir)
     }
-    fn make_large_db() -> MovieDB {
-        let data_dir = PathBuf::from("data/new_large");
-        make_db(&data_dir)
-    }
     #[tokio::test]
     async fn cruise_hanks() {
         let source_id: usize = 129;
@@ -35,18 +37,75 @@ mod test {
 
         let db = make_small_db();
 
-        let path = shortest_path(source_id, target_id, &db).await.unwrap();
+        let path = shortest_path(
+            source_id,
+            target_id,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap()
+        .0;
         assert_eq!(path.len(), 3);
     }
     #[tokio::test]
-    #[ignore = "takes too long"]
+    #[ignore = "needs data/new_large"]
     async fn massey_fox() {
+        let data_dir = PathBuf::from("data/new_large");
         let source_id = 5368041;
         let target_id = 289114;
 
-        let db = make_large_db();
+        let db = make_db(&data_dir);
 
-        let path = shortest_path(source_id, target_id, &db).await.unwrap();
+        let start = Instant::now();
+        let path = shortest_path(
+            source_id,
+            target_id,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap()
+        .0;
         assert_eq!(path.len(), 8);
+        assert!(
+            start.elapsed() < SEARCH_TIME_LIMIT,
+            "took {:?}",
+            start.elapsed()
+        );
+    }
+
+    /// The search that used to take too long to run as a test, on a graph
+    /// about the size of `data/new_large`.
+    #[tokio::test]
+    async fn large_random_graph() {
+        let num_actors = 500_000;
+        let db = MovieDB {
+            connections: MovieDBBuilder::get_connections(random_credits(0, num_actors, 3)),
+            actors: HashMap::new(),
+            movies: HashMap::new(),
+        };
+
+        let start = Instant::now();
+        let (path, stats) = shortest_path(
+            0,
+            num_actors - 1,
+            &db,
+            &SearchOptions::default(),
+            &SearchLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert!(
+            start.elapsed() < SEARCH_TIME_LIMIT,
+            "took {:?}",
+            start.elapsed()
+        );
+        assert_eq!(path[0].actor_id, 0);
+        assert_eq!(path.last().unwrap().actor_id, num_actors - 1);
+        // meeting in the middle expands a sliver of the graph
+        assert!(stats.nodes_explored < num_actors / 100);
     }
 }